@@ -0,0 +1,21 @@
+use anyhow::Result;
+
+use nl80211scan::Nl80211;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut nl80211 = Nl80211::new()?;
+
+    for iface in nl80211.interfaces().await? {
+        println!(
+            "{} wdev {} {:?} {} {}",
+            iface.name.as_deref().unwrap_or("-"),
+            iface.wdev,
+            iface.iftype,
+            iface.mac_address,
+            iface.ssid.as_deref().unwrap_or("")
+        );
+    }
+
+    Ok(())
+}
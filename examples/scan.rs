@@ -1,12 +1,23 @@
 use anyhow::Result;
-use nl80211scan;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let stations = nl80211scan::scan("wlan0").await?;
 
     for station in stations {
-        println!("{} {}%", station.ssid, station.quality);
+        let ssid = if station.hidden {
+            "<hidden>"
+        } else {
+            &station.ssid
+        };
+        let signal = match station.signal {
+            Some(signal) => format!("{} {}%", signal, signal.quality()),
+            None => "no signal".to_owned(),
+        };
+        println!(
+            "{} {} {} MHz {} {:?}",
+            station.bssid, ssid, station.frequency, signal, station.security
+        );
     }
 
     Ok(())
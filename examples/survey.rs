@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::{pin_mut, StreamExt};
+
+use nl80211scan::Nl80211;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut nl80211 = Nl80211::new()?;
+    let iface = nl80211.interface("wlan0").await?;
+
+    let snapshots = nl80211.scan_stream(&iface, Duration::from_secs(10));
+    pin_mut!(snapshots);
+
+    while let Some(stations) = snapshots.next().await {
+        let stations = stations?;
+        println!("{} stations", stations.len());
+        for station in stations {
+            let signal = station.signal.map(|signal| signal.to_string());
+            println!(
+                "  {} {} {} MHz {}",
+                station.bssid,
+                station.ssid,
+                station.frequency,
+                signal.as_deref().unwrap_or("no signal")
+            );
+        }
+    }
+
+    Ok(())
+}
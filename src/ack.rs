@@ -0,0 +1,300 @@
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+
+use byteorder::{ByteOrder, NativeEndian};
+
+use crate::error::{Error, Result};
+
+const NETLINK_CAP_ACK: libc::c_int = 10;
+const NETLINK_EXT_ACK: libc::c_int = 11;
+
+const NLMSG_ERROR: u16 = 2;
+const NLM_F_CAPPED: u16 = 0x100;
+const NLM_F_ACK_TLVS: u16 = 0x200;
+const NLMSGERR_ATTR_MSG: u16 = 1;
+
+const NLMSG_HDRLEN: usize = 16;
+const NLA_HDRLEN: usize = 4;
+
+/// Asks the kernel to attach extended acknowledgement attributes, such as a human readable
+/// error message, to error responses, and to leave out the echoed request.
+pub(crate) fn enable_extended_ack(socket: &impl AsRawFd) -> Result<()> {
+    for option in [NETLINK_EXT_ACK, NETLINK_CAP_ACK] {
+        let enable: libc::c_int = 1;
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_NETLINK,
+                option,
+                &enable as *const _ as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        // Kernels older than 4.12 do not know about extended acks, which only costs us the
+        // error message.
+        if ret < 0 && io::Error::last_os_error().raw_os_error() != Some(libc::ENOPROTOOPT) {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes the `NLMSG_ERROR` acknowledgement of a request sent with `NLM_F_ACK`, turning a
+/// non-zero errno into an error carrying the extended ack message, if any.
+pub(crate) fn parse_ack(buf: &[u8]) -> Result<()> {
+    find_error(buf)
+        .unwrap_or_else(|| Err(Error::Protocol("No acknowledgement received".to_owned())))
+}
+
+/// Fails with the decoded errno if the datagram contains an `NLMSG_ERROR` message with a
+/// non-zero error code.
+pub(crate) fn check_error(buf: &[u8]) -> Result<()> {
+    find_error(buf).unwrap_or(Ok(()))
+}
+
+/// Returns the sequence number of the first message of a datagram. The messages of a reply all
+/// carry the sequence number of the request.
+pub(crate) fn sequence_number(buf: &[u8]) -> Option<u32> {
+    buf.get(8..12).map(NativeEndian::read_u32)
+}
+
+fn find_error(buf: &[u8]) -> Option<Result<()>> {
+    let mut offset = 0;
+
+    while buf.len() >= offset + NLMSG_HDRLEN {
+        let msg = &buf[offset..];
+        let len = NativeEndian::read_u32(&msg[0..4]) as usize;
+        let nl_type = NativeEndian::read_u16(&msg[4..6]);
+        let flags = NativeEndian::read_u16(&msg[6..8]);
+
+        if len < NLMSG_HDRLEN || len > msg.len() {
+            break;
+        }
+
+        if nl_type == NLMSG_ERROR {
+            return Some(parse_error(&msg[NLMSG_HDRLEN..len], flags));
+        }
+
+        offset += align(len);
+    }
+
+    None
+}
+
+fn parse_error(payload: &[u8], flags: u16) -> Result<()> {
+    let truncated = || Error::Protocol("Truncated acknowledgement".to_owned());
+
+    let errno = -payload
+        .get(0..4)
+        .map(NativeEndian::read_i32)
+        .ok_or_else(truncated)?;
+    if errno == 0 {
+        return Ok(());
+    }
+
+    let request = payload.get(4..).ok_or_else(truncated)?;
+    let request_len = if flags & NLM_F_CAPPED != 0 {
+        NLMSG_HDRLEN
+    } else {
+        request
+            .get(0..4)
+            .map(|len| NativeEndian::read_u32(len) as usize)
+            .ok_or_else(truncated)?
+    };
+
+    let message = if flags & NLM_F_ACK_TLVS != 0 {
+        request.get(align(request_len)..).and_then(extract_message)
+    } else {
+        None
+    };
+
+    Err(Error::from_errno(errno, message))
+}
+
+fn extract_message(mut tlvs: &[u8]) -> Option<String> {
+    while tlvs.len() >= NLA_HDRLEN {
+        let len = NativeEndian::read_u16(&tlvs[0..2]) as usize;
+        let nla_type = NativeEndian::read_u16(&tlvs[2..4]);
+        if len < NLA_HDRLEN || len > tlvs.len() {
+            return None;
+        }
+
+        if nla_type == NLMSGERR_ATTR_MSG {
+            let text = &tlvs[NLA_HDRLEN..len];
+            let text = text.split(|&b| b == 0).next().unwrap_or_default();
+            return Some(String::from_utf8_lossy(text).into_owned());
+        }
+
+        tlvs = tlvs.get(align(len)..)?;
+    }
+
+    None
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NLMSG_DONE: u16 = 3;
+    const GENL_ID: u16 = 0x1c;
+
+    fn nlmsg(nl_type: u16, flags: u16, seq: u32, payload: &[u8]) -> Vec<u8> {
+        let len = NLMSG_HDRLEN + payload.len();
+        let mut msg = vec![0; NLMSG_HDRLEN];
+        NativeEndian::write_u32(&mut msg[0..4], len as u32);
+        NativeEndian::write_u16(&mut msg[4..6], nl_type);
+        NativeEndian::write_u16(&mut msg[6..8], flags);
+        NativeEndian::write_u32(&mut msg[8..12], seq);
+        msg.extend_from_slice(payload);
+        msg.resize(align(len), 0);
+        msg
+    }
+
+    fn nla(nla_type: u16, payload: &[u8]) -> Vec<u8> {
+        let len = NLA_HDRLEN + payload.len();
+        let mut attr = vec![0; NLA_HDRLEN];
+        NativeEndian::write_u16(&mut attr[0..2], len as u16);
+        NativeEndian::write_u16(&mut attr[2..4], nla_type);
+        attr.extend_from_slice(payload);
+        attr.resize(align(len), 0);
+        attr
+    }
+
+    /// Builds the `NLMSG_ERROR` reply to `request`, echoing only its header if `capped`.
+    fn error(errno: i32, request: &[u8], capped: bool, tlvs: &[u8]) -> Vec<u8> {
+        let mut payload = vec![0; 4];
+        NativeEndian::write_i32(&mut payload, -errno);
+        match capped {
+            true => payload.extend_from_slice(&request[..NLMSG_HDRLEN]),
+            false => payload.extend_from_slice(request),
+        }
+        payload.extend_from_slice(tlvs);
+
+        let mut flags = 0;
+        if capped {
+            flags |= NLM_F_CAPPED;
+        }
+        if !tlvs.is_empty() {
+            flags |= NLM_F_ACK_TLVS;
+        }
+        nlmsg(NLMSG_ERROR, flags, 7, &payload)
+    }
+
+    fn request() -> Vec<u8> {
+        nlmsg(GENL_ID, 0x5, 7, &[0x21, 0x01, 0x00, 0x00, 0xaa, 0xbb])
+    }
+
+    #[test]
+    fn zero_errno_is_success() {
+        assert!(parse_ack(&error(0, &request(), true, &[])).is_ok());
+    }
+
+    #[test]
+    fn decodes_capped_extended_ack() {
+        let tlvs = nla(NLMSGERR_ATTR_MSG, b"unsupported scan flags\0");
+
+        let err = parse_ack(&error(libc::EOPNOTSUPP, &request(), true, &tlvs)).unwrap_err();
+
+        match err {
+            Error::Netlink { errno, message } => {
+                assert_eq!(errno, libc::EOPNOTSUPP);
+                assert_eq!(message.as_deref(), Some("unsupported scan flags"));
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn skips_echoed_request_before_tlvs() {
+        let mut tlvs = nla(2, &[0x18, 0x00, 0x00, 0x00]);
+        tlvs.extend(nla(NLMSGERR_ATTR_MSG, b"invalid SSID\0"));
+
+        let err = parse_ack(&error(libc::EINVAL, &request(), false, &tlvs)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Netlink {
+                errno: libc::EINVAL,
+                message: Some(message),
+            } if message == "invalid SSID"
+        ));
+    }
+
+    #[test]
+    fn error_without_tlvs_has_no_message() {
+        let err = parse_ack(&error(libc::ENODEV, &request(), false, &[])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Netlink {
+                errno: libc::ENODEV,
+                message: None,
+            }
+        ));
+    }
+
+    #[test]
+    fn maps_well_known_errnos() {
+        assert!(matches!(
+            parse_ack(&error(libc::EBUSY, &request(), true, &[])),
+            Err(Error::DeviceBusy(None))
+        ));
+        assert!(matches!(
+            parse_ack(&error(libc::EPERM, &request(), true, &[])),
+            Err(Error::PermissionDenied(None))
+        ));
+    }
+
+    #[test]
+    fn well_known_errnos_keep_message() {
+        let tlvs = nla(NLMSGERR_ATTR_MSG, b"scan already in progress\0");
+
+        let err = parse_ack(&error(libc::EBUSY, &request(), true, &tlvs)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::DeviceBusy(Some(ref message)) if message == "scan already in progress"
+        ));
+    }
+
+    #[test]
+    fn truncated_error_is_a_protocol_error() {
+        let msg = nlmsg(NLMSG_ERROR, 0, 7, &[0xea, 0xff]);
+
+        assert!(matches!(parse_ack(&msg), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn finds_error_after_other_messages() {
+        let mut buf = nlmsg(GENL_ID, 0, 7, &[0x22, 0x01, 0x00, 0x00]);
+        buf.extend(error(libc::ENOBUFS, &request(), true, &[]));
+
+        assert!(matches!(
+            check_error(&buf),
+            Err(Error::Netlink {
+                errno: libc::ENOBUFS,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn missing_ack() {
+        let buf = nlmsg(NLMSG_DONE, 0, 7, &[0; 4]);
+
+        assert!(check_error(&buf).is_ok());
+        assert!(matches!(parse_ack(&buf), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn reads_sequence_number() {
+        assert_eq!(sequence_number(&request()), Some(7));
+        assert_eq!(sequence_number(&[0; 8]), None);
+    }
+}
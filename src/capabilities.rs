@@ -0,0 +1,362 @@
+use crate::consts;
+use crate::ie::{self, Element};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WifiGeneration {
+    /// 802.11a/b/g without HT support.
+    Legacy,
+    /// 802.11n (HT).
+    Wifi4,
+    /// 802.11ac (VHT).
+    Wifi5,
+    /// 802.11ax (HE).
+    Wifi6,
+    /// 802.11be (EHT).
+    Wifi7,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelWidth {
+    Mhz20,
+    Mhz40,
+    Mhz80,
+    Mhz80P80,
+    Mhz160,
+    Mhz320,
+}
+
+impl ChannelWidth {
+    /// Converts an `nl80211_chan_width` value, ignoring the 5/10 MHz and S1G widths.
+    pub(crate) fn from_nl80211(width: ::std::os::raw::c_uint) -> Option<Self> {
+        match width {
+            consts::NL80211_CHAN_WIDTH_20_NOHT | consts::NL80211_CHAN_WIDTH_20 => {
+                Some(ChannelWidth::Mhz20)
+            }
+            consts::NL80211_CHAN_WIDTH_40 => Some(ChannelWidth::Mhz40),
+            consts::NL80211_CHAN_WIDTH_80 => Some(ChannelWidth::Mhz80),
+            consts::NL80211_CHAN_WIDTH_80P80 => Some(ChannelWidth::Mhz80P80),
+            consts::NL80211_CHAN_WIDTH_160 => Some(ChannelWidth::Mhz160),
+            consts::NL80211_CHAN_WIDTH_320 => Some(ChannelWidth::Mhz320),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecondaryChannelOffset {
+    None,
+    Above,
+    Below,
+}
+
+/// PHY capabilities and operating channel of a BSS, derived from its HT, VHT, HE and EHT
+/// elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    pub generation: WifiGeneration,
+    pub max_spatial_streams: u8,
+    pub channel_width: ChannelWidth,
+    pub secondary_channel_offset: SecondaryChannelOffset,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            generation: WifiGeneration::Legacy,
+            max_spatial_streams: 1,
+            channel_width: ChannelWidth::Mhz20,
+            secondary_channel_offset: SecondaryChannelOffset::None,
+        }
+    }
+}
+
+impl Capabilities {
+    pub fn parse(ies: &[u8]) -> Self {
+        let mut capabilities = Capabilities::default();
+        let mut ht_width = None;
+        let mut vht_width = None;
+        let mut he_width = None;
+        let mut eht_width = None;
+
+        for element in ie::parse(ies).filter_map(Result::ok) {
+            match element {
+                Element::HtCapabilities(data) => {
+                    capabilities.raise(WifiGeneration::Wifi4, ht_spatial_streams(data));
+                }
+                Element::HtOperation(data) => {
+                    let (offset, width) = parse_ht_operation(data);
+                    capabilities.secondary_channel_offset = offset;
+                    ht_width = Some(width);
+                }
+                Element::VhtCapabilities(data) => {
+                    let streams = mcs_map_spatial_streams(u16::from_le_bytes([data[4], data[5]]));
+                    capabilities.raise(WifiGeneration::Wifi5, streams);
+                }
+                Element::VhtOperation(data) => vht_width = parse_vht_operation(data),
+                Element::HeCapabilities(data) => {
+                    let streams = mcs_map_spatial_streams(u16::from_le_bytes([data[17], data[18]]));
+                    capabilities.raise(WifiGeneration::Wifi6, streams);
+                }
+                Element::HeOperation(data) => he_width = parse_he_operation(data),
+                Element::EhtCapabilities(data) => {
+                    let streams = data.get(11).map(|nss| nss & 0x0f).unwrap_or_default();
+                    capabilities.raise(WifiGeneration::Wifi7, streams);
+                }
+                Element::EhtOperation(data) => eht_width = parse_eht_operation(data),
+                _ => {}
+            }
+        }
+
+        capabilities.channel_width = eht_width
+            .or(he_width)
+            .or(vht_width)
+            .or(ht_width)
+            .unwrap_or(ChannelWidth::Mhz20);
+
+        capabilities
+    }
+
+    fn raise(&mut self, generation: WifiGeneration, spatial_streams: u8) {
+        self.generation = self.generation.max(generation);
+        self.max_spatial_streams = self.max_spatial_streams.max(spatial_streams);
+    }
+}
+
+fn ht_spatial_streams(data: &[u8]) -> u8 {
+    // The Rx MCS bitmask starts at offset 3, one byte per spatial stream.
+    data[3..7].iter().take_while(|&&mcs| mcs != 0).count() as u8
+}
+
+/// Counts the spatial streams with any supported MCS in a VHT or HE MCS map, which holds two
+/// bits per stream with 3 meaning not supported.
+fn mcs_map_spatial_streams(map: u16) -> u8 {
+    (0..8)
+        .filter(|nss| (map >> (nss * 2)) & 0x3 != 0x3)
+        .map(|nss| nss + 1)
+        .max()
+        .unwrap_or_default()
+}
+
+fn parse_ht_operation(data: &[u8]) -> (SecondaryChannelOffset, ChannelWidth) {
+    let offset = match data[1] & 0x3 {
+        1 => SecondaryChannelOffset::Above,
+        3 => SecondaryChannelOffset::Below,
+        _ => SecondaryChannelOffset::None,
+    };
+    let any_width = data[1] & 0x4 != 0;
+    let width = if any_width && offset != SecondaryChannelOffset::None {
+        ChannelWidth::Mhz40
+    } else {
+        ChannelWidth::Mhz20
+    };
+    (offset, width)
+}
+
+fn parse_vht_operation(data: &[u8]) -> Option<ChannelWidth> {
+    match data[0] {
+        1 => Some(vht_80_160_width(data[1], data[2])),
+        2 => Some(ChannelWidth::Mhz160),
+        3 => Some(ChannelWidth::Mhz80P80),
+        _ => None,
+    }
+}
+
+/// Tells 80, 160 and 80+80 MHz apart from the two channel centre frequency segments, as in
+/// IEEE 802.11 Table 9-274.
+fn vht_80_160_width(ccfs0: u8, ccfs1: u8) -> ChannelWidth {
+    match ccfs1.abs_diff(ccfs0) {
+        _ if ccfs1 == 0 => ChannelWidth::Mhz80,
+        8 => ChannelWidth::Mhz160,
+        diff if diff > 16 => ChannelWidth::Mhz80P80,
+        _ => ChannelWidth::Mhz80,
+    }
+}
+
+fn parse_he_operation(data: &[u8]) -> Option<ChannelWidth> {
+    let params = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+    let vht_info_present = params & (1 << 14) != 0;
+    let co_hosted_bss = params & (1 << 15) != 0;
+    let six_ghz_info_present = params & (1 << 17) != 0;
+
+    if !six_ghz_info_present {
+        return None;
+    }
+
+    // Skip the parameters, BSS color, basic HE-MCS set and the optional VHT operation
+    // information and max co-hosted BSSID indicator fields.
+    let offset = 6 + if vht_info_present { 3 } else { 0 } + usize::from(co_hosted_bss);
+    let info = data.get(offset..offset + 5)?;
+    let width = match info[1] & 0x3 {
+        0 => ChannelWidth::Mhz20,
+        1 => ChannelWidth::Mhz40,
+        2 => ChannelWidth::Mhz80,
+        _ => vht_80_160_width(info[2], info[3]),
+    };
+    Some(width)
+}
+
+fn parse_eht_operation(data: &[u8]) -> Option<ChannelWidth> {
+    let info_present = data[0] & 0x1 != 0;
+    if !info_present {
+        return None;
+    }
+
+    // The EHT Operation Information follows the parameters and basic EHT-MCS and NSS set.
+    let control = data.get(5)?;
+    match control & 0x7 {
+        0 => Some(ChannelWidth::Mhz20),
+        1 => Some(ChannelWidth::Mhz40),
+        2 => Some(ChannelWidth::Mhz80),
+        3 => Some(ChannelWidth::Mhz160),
+        4 => Some(ChannelWidth::Mhz320),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(id: u8, data: &[u8]) -> Vec<u8> {
+        let mut element = vec![id, data.len() as u8];
+        element.extend_from_slice(data);
+        element
+    }
+
+    fn extension(id_ext: u8, data: &[u8]) -> Vec<u8> {
+        let mut body = vec![id_ext];
+        body.extend_from_slice(data);
+        element(ie::EID_EXTENSION, &body)
+    }
+
+    fn ht_capabilities(mcs: [u8; 4]) -> Vec<u8> {
+        let mut data = [0; 26];
+        data[3..7].copy_from_slice(&mcs);
+        element(ie::EID_HT_CAPABILITIES, &data)
+    }
+
+    fn ht_operation(params: u8) -> Vec<u8> {
+        let mut data = [0; 22];
+        data[0] = 36;
+        data[1] = params;
+        element(ie::EID_HT_OPERATION, &data)
+    }
+
+    fn vht_capabilities(rx_mcs_map: u16) -> Vec<u8> {
+        let mut data = [0; 12];
+        data[4..6].copy_from_slice(&rx_mcs_map.to_le_bytes());
+        element(ie::EID_VHT_CAPABILITIES, &data)
+    }
+
+    #[test]
+    fn no_elements_is_legacy() {
+        assert_eq!(Capabilities::parse(&[]), Capabilities::default());
+    }
+
+    #[test]
+    fn parses_ht() {
+        let mut ies = ht_capabilities([0xff, 0xff, 0x00, 0x00]);
+        ies.extend(ht_operation(0x07));
+
+        let capabilities = Capabilities::parse(&ies);
+
+        assert_eq!(capabilities.generation, WifiGeneration::Wifi4);
+        assert_eq!(capabilities.max_spatial_streams, 2);
+        assert_eq!(capabilities.channel_width, ChannelWidth::Mhz40);
+        assert_eq!(
+            capabilities.secondary_channel_offset,
+            SecondaryChannelOffset::Below
+        );
+    }
+
+    #[test]
+    fn ht_without_secondary_channel_is_20_mhz() {
+        let capabilities = Capabilities::parse(&ht_operation(0x04));
+
+        assert_eq!(capabilities.channel_width, ChannelWidth::Mhz20);
+        assert_eq!(
+            capabilities.secondary_channel_offset,
+            SecondaryChannelOffset::None
+        );
+    }
+
+    #[test]
+    fn parses_vht() {
+        let mut ies = ht_capabilities([0xff, 0x00, 0x00, 0x00]);
+        ies.extend(ht_operation(0x05));
+        ies.extend(vht_capabilities(0xffea));
+        ies.extend(element(ie::EID_VHT_OPERATION, &[1, 42, 50, 0, 0]));
+
+        let capabilities = Capabilities::parse(&ies);
+
+        assert_eq!(capabilities.generation, WifiGeneration::Wifi5);
+        assert_eq!(capabilities.max_spatial_streams, 3);
+        assert_eq!(capabilities.channel_width, ChannelWidth::Mhz160);
+        assert_eq!(
+            capabilities.secondary_channel_offset,
+            SecondaryChannelOffset::Above
+        );
+    }
+
+    #[test]
+    fn tells_vht_widths_apart() {
+        assert_eq!(vht_80_160_width(42, 0), ChannelWidth::Mhz80);
+        assert_eq!(vht_80_160_width(42, 50), ChannelWidth::Mhz160);
+        assert_eq!(vht_80_160_width(42, 155), ChannelWidth::Mhz80P80);
+        assert_eq!(
+            parse_vht_operation(&[0, 0, 0, 0, 0]),
+            None,
+            "20/40 MHz defers to the HT operation"
+        );
+    }
+
+    #[test]
+    fn parses_he_6ghz_operation() {
+        let mut he_capabilities = [0; 21];
+        he_capabilities[17..19].copy_from_slice(&0xfffe_u16.to_le_bytes());
+        let mut ies = extension(ie::EID_EXT_HE_CAPABILITIES, &he_capabilities);
+        ies.extend(extension(
+            ie::EID_EXT_HE_OPERATION,
+            &[0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 37, 0x03, 39, 47, 0x00],
+        ));
+
+        let capabilities = Capabilities::parse(&ies);
+
+        assert_eq!(capabilities.generation, WifiGeneration::Wifi6);
+        assert_eq!(capabilities.max_spatial_streams, 1);
+        assert_eq!(capabilities.channel_width, ChannelWidth::Mhz160);
+    }
+
+    #[test]
+    fn he_operation_without_6ghz_info_has_no_width() {
+        assert_eq!(parse_he_operation(&[0; 6]), None);
+    }
+
+    #[test]
+    fn parses_eht() {
+        let mut eht_capabilities = [0; 12];
+        eht_capabilities[11] = 0x44;
+        let mut ies = vht_capabilities(0xfffa);
+        ies.extend(element(ie::EID_VHT_OPERATION, &[1, 42, 0, 0, 0]));
+        ies.extend(extension(ie::EID_EXT_EHT_CAPABILITIES, &eht_capabilities));
+        ies.extend(extension(
+            ie::EID_EXT_EHT_OPERATION,
+            &[0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00],
+        ));
+
+        let capabilities = Capabilities::parse(&ies);
+
+        assert_eq!(capabilities.generation, WifiGeneration::Wifi7);
+        assert_eq!(capabilities.max_spatial_streams, 4);
+        assert_eq!(capabilities.channel_width, ChannelWidth::Mhz320);
+    }
+
+    #[test]
+    fn invalid_elements_are_ignored() {
+        let mut ies = element(ie::EID_HT_CAPABILITIES, &[0xff; 10]);
+        ies.extend(element(ie::EID_VHT_OPERATION, &[1, 42]));
+        ies.extend(extension(ie::EID_EXT_HE_CAPABILITIES, &[0; 4]));
+
+        assert_eq!(Capabilities::parse(&ies), Capabilities::default());
+    }
+}
@@ -1004,6 +1004,7 @@ pub const NL80211_CHAN_WIDTH_2: nl80211_chan_width = 9;
 pub const NL80211_CHAN_WIDTH_4: nl80211_chan_width = 10;
 pub const NL80211_CHAN_WIDTH_8: nl80211_chan_width = 11;
 pub const NL80211_CHAN_WIDTH_16: nl80211_chan_width = 12;
+pub const NL80211_CHAN_WIDTH_320: nl80211_chan_width = 13;
 pub type nl80211_chan_width = ::std::os::raw::c_uint;
 pub const NL80211_BSS_CHAN_WIDTH_20: nl80211_bss_scan_width = 0;
 pub const NL80211_BSS_CHAN_WIDTH_10: nl80211_bss_scan_width = 1;
@@ -1032,8 +1033,12 @@ pub const NL80211_BSS_PARENT_TSF: nl80211_bss = 17;
 pub const NL80211_BSS_PARENT_BSSID: nl80211_bss = 18;
 pub const NL80211_BSS_CHAIN_SIGNAL: nl80211_bss = 19;
 pub const NL80211_BSS_FREQUENCY_OFFSET: nl80211_bss = 20;
-pub const __NL80211_BSS_AFTER_LAST: nl80211_bss = 21;
-pub const NL80211_BSS_MAX: nl80211_bss = 20;
+pub const NL80211_BSS_MLO_LINK_ID: nl80211_bss = 21;
+pub const NL80211_BSS_MLD_ADDR: nl80211_bss = 22;
+pub const NL80211_BSS_USE_FOR: nl80211_bss = 23;
+pub const NL80211_BSS_CANNOT_USE_REASONS: nl80211_bss = 24;
+pub const __NL80211_BSS_AFTER_LAST: nl80211_bss = 25;
+pub const NL80211_BSS_MAX: nl80211_bss = 24;
 pub type nl80211_bss = ::std::os::raw::c_uint;
 pub const NL80211_BSS_STATUS_AUTHENTICATED: nl80211_bss_status = 0;
 pub const NL80211_BSS_STATUS_ASSOCIATED: nl80211_bss_status = 1;
@@ -484,6 +484,77 @@ pub enum Nl80211Bss {
     ParentBssid = NL80211_BSS_PARENT_BSSID as _,
     ChainSignal = NL80211_BSS_CHAIN_SIGNAL as _,
     FrequencyOffset = NL80211_BSS_FREQUENCY_OFFSET as _,
+    MloLinkId = NL80211_BSS_MLO_LINK_ID as _,
+    MldAddr = NL80211_BSS_MLD_ADDR as _,
+    UseFor = NL80211_BSS_USE_FOR as _,
+    CannotUseReasons = NL80211_BSS_CANNOT_USE_REASONS as _,
 }
 
 impl neli::consts::genl::NlAttrType for Nl80211Bss {}
+
+#[neli_enum(serialized_type = "u16")]
+pub enum Nl80211BandAttr {
+    Freqs = NL80211_BAND_ATTR_FREQS as _,
+    Rates = NL80211_BAND_ATTR_RATES as _,
+    HtMcsSet = NL80211_BAND_ATTR_HT_MCS_SET as _,
+    HtCapa = NL80211_BAND_ATTR_HT_CAPA as _,
+    HtAmpduFactor = NL80211_BAND_ATTR_HT_AMPDU_FACTOR as _,
+    HtAmpduDensity = NL80211_BAND_ATTR_HT_AMPDU_DENSITY as _,
+    VhtMcsSet = NL80211_BAND_ATTR_VHT_MCS_SET as _,
+    VhtCapa = NL80211_BAND_ATTR_VHT_CAPA as _,
+    IftypeData = NL80211_BAND_ATTR_IFTYPE_DATA as _,
+    EdmgChannels = NL80211_BAND_ATTR_EDMG_CHANNELS as _,
+    EdmgBwConfig = NL80211_BAND_ATTR_EDMG_BW_CONFIG as _,
+}
+
+impl neli::consts::genl::NlAttrType for Nl80211BandAttr {}
+
+#[neli_enum(serialized_type = "u16")]
+pub enum Nl80211FrequencyAttr {
+    Freq = NL80211_FREQUENCY_ATTR_FREQ as _,
+    Disabled = NL80211_FREQUENCY_ATTR_DISABLED as _,
+    NoIr = NL80211_FREQUENCY_ATTR_NO_IR as _,
+    NoIbss = __NL80211_FREQUENCY_ATTR_NO_IBSS as _,
+    Radar = NL80211_FREQUENCY_ATTR_RADAR as _,
+    MaxTxPower = NL80211_FREQUENCY_ATTR_MAX_TX_POWER as _,
+    DfsState = NL80211_FREQUENCY_ATTR_DFS_STATE as _,
+    DfsTime = NL80211_FREQUENCY_ATTR_DFS_TIME as _,
+    NoHt40Minus = NL80211_FREQUENCY_ATTR_NO_HT40_MINUS as _,
+    NoHt40Plus = NL80211_FREQUENCY_ATTR_NO_HT40_PLUS as _,
+    No80Mhz = NL80211_FREQUENCY_ATTR_NO_80MHZ as _,
+    No160Mhz = NL80211_FREQUENCY_ATTR_NO_160MHZ as _,
+    DfsCacTime = NL80211_FREQUENCY_ATTR_DFS_CAC_TIME as _,
+    IndoorOnly = NL80211_FREQUENCY_ATTR_INDOOR_ONLY as _,
+    IrConcurrent = NL80211_FREQUENCY_ATTR_IR_CONCURRENT as _,
+    No20Mhz = NL80211_FREQUENCY_ATTR_NO_20MHZ as _,
+    No10Mhz = NL80211_FREQUENCY_ATTR_NO_10MHZ as _,
+    Wmm = NL80211_FREQUENCY_ATTR_WMM as _,
+    NoHe = NL80211_FREQUENCY_ATTR_NO_HE as _,
+    Offset = NL80211_FREQUENCY_ATTR_OFFSET as _,
+    Mhz1 = NL80211_FREQUENCY_ATTR_1MHZ as _,
+    Mhz2 = NL80211_FREQUENCY_ATTR_2MHZ as _,
+    Mhz4 = NL80211_FREQUENCY_ATTR_4MHZ as _,
+    Mhz8 = NL80211_FREQUENCY_ATTR_8MHZ as _,
+    Mhz16 = NL80211_FREQUENCY_ATTR_16MHZ as _,
+}
+
+impl neli::consts::genl::NlAttrType for Nl80211FrequencyAttr {}
+
+#[neli_enum(serialized_type = "u16")]
+pub enum Nl80211SchedScanMatchAttr {
+    Ssid = NL80211_SCHED_SCAN_MATCH_ATTR_SSID as _,
+    Rssi = NL80211_SCHED_SCAN_MATCH_ATTR_RSSI as _,
+    RelativeRssi = NL80211_SCHED_SCAN_MATCH_ATTR_RELATIVE_RSSI as _,
+    RssiAdjust = NL80211_SCHED_SCAN_MATCH_ATTR_RSSI_ADJUST as _,
+    Bssid = NL80211_SCHED_SCAN_MATCH_ATTR_BSSID as _,
+}
+
+impl neli::consts::genl::NlAttrType for Nl80211SchedScanMatchAttr {}
+
+#[neli_enum(serialized_type = "u16")]
+pub enum Nl80211SchedScanPlan {
+    Interval = NL80211_SCHED_SCAN_PLAN_INTERVAL as _,
+    Iterations = NL80211_SCHED_SCAN_PLAN_ITERATIONS as _,
+}
+
+impl neli::consts::genl::NlAttrType for Nl80211SchedScanPlan {}
@@ -0,0 +1,130 @@
+use std::array::TryFromSliceError;
+use std::fmt;
+use std::io;
+
+use neli::err::{DeError, NlError, SerError};
+
+use crate::ie::ParseError;
+use crate::interface::InterfaceId;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The nl80211 generic netlink family is not registered, usually because cfg80211 is not
+    /// loaded.
+    FamilyNotFound,
+    InterfaceNotFound(InterfaceId),
+    /// The request needs `CAP_NET_ADMIN` (EPERM), along with the extended ack message, if any.
+    PermissionDenied(Option<String>),
+    /// The device is busy, e.g. a scan is already running (EBUSY), along with the extended ack
+    /// message, if any.
+    DeviceBusy(Option<String>),
+    /// The kernel rejected a request with an errno not covered by the other variants, along
+    /// with the extended ack message explaining why, if the kernel provided one.
+    Netlink {
+        errno: i32,
+        message: Option<String>,
+    },
+    ScanAborted,
+    ScanTimedOut,
+    /// The scan request is not supported by the wiphy or is malformed.
+    InvalidScanRequest(String),
+    /// A netlink message or information element could not be parsed.
+    Parse(String),
+    /// A netlink message could not be built, or the kernel response violated the protocol.
+    Protocol(String),
+    Io(io::Error),
+}
+
+impl Error {
+    pub(crate) fn from_errno(errno: i32, message: Option<String>) -> Self {
+        match errno {
+            libc::EPERM => Error::PermissionDenied(message),
+            libc::EBUSY => Error::DeviceBusy(message),
+            errno => Error::Netlink { errno, message },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::FamilyNotFound => write!(f, "nl80211 family not found"),
+            Error::InterfaceNotFound(id) => write!(f, "Interface {:?} not found", id),
+            Error::PermissionDenied(Some(message)) => {
+                write!(f, "Operation not permitted: {}", message)
+            }
+            Error::PermissionDenied(None) => write!(f, "Operation not permitted"),
+            Error::DeviceBusy(Some(message)) => write!(f, "Device or resource busy: {}", message),
+            Error::DeviceBusy(None) => write!(f, "Device or resource busy"),
+            Error::Netlink {
+                errno,
+                message: Some(message),
+            } => write!(f, "{}: {}", io::Error::from_raw_os_error(*errno), message),
+            Error::Netlink { errno, .. } => {
+                write!(f, "{}", io::Error::from_raw_os_error(*errno))
+            }
+            Error::ScanAborted => write!(f, "Scan aborted"),
+            Error::ScanTimedOut => write!(f, "Scan timed out"),
+            Error::InvalidScanRequest(msg) => write!(f, "Invalid scan request: {}", msg),
+            Error::Parse(msg) => write!(f, "Parse error: {}", msg),
+            Error::Protocol(msg) => write!(f, "Netlink protocol error: {}", msg),
+            Error::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<DeError> for Error {
+    fn from(err: DeError) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+impl From<SerError> for Error {
+    fn from(err: SerError) -> Self {
+        Error::Protocol(err.to_string())
+    }
+}
+
+impl<T, P> From<NlError<T, P>> for Error {
+    fn from(err: NlError<T, P>) -> Self {
+        match err {
+            NlError::Nlmsgerr(err) => Error::from_errno(-err.error, None),
+            NlError::Ser(err) => err.into(),
+            NlError::De(err) => err.into(),
+            NlError::Msg(msg) => Error::Protocol(msg),
+            NlError::Wrapped(err) => Error::Protocol(err.to_string()),
+            NlError::NoAck => Error::Protocol("No acknowledgement received".to_owned()),
+            NlError::BadSeq => Error::Protocol("Unexpected sequence number".to_owned()),
+            NlError::BadPid => Error::Protocol("Unexpected port ID".to_owned()),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+impl From<TryFromSliceError> for Error {
+    fn from(err: TryFromSliceError) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::consts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Band {
+    Band2GHz,
+    Band5GHz,
+    Band60GHz,
+    Band6GHz,
+    BandS1GHz,
+}
+
+impl Band {
+    /// Converts an `nl80211_band` value, ignoring bands added to the kernel after this crate.
+    pub(crate) fn from_nl80211(band: ::std::os::raw::c_uint) -> Option<Self> {
+        match band {
+            consts::NL80211_BAND_2GHZ => Some(Band::Band2GHz),
+            consts::NL80211_BAND_5GHZ => Some(Band::Band5GHz),
+            consts::NL80211_BAND_60GHZ => Some(Band::Band60GHz),
+            consts::NL80211_BAND_6GHZ => Some(Band::Band6GHz),
+            consts::NL80211_BAND_S1GHZ => Some(Band::BandS1GHz),
+            _ => None,
+        }
+    }
+
+    pub fn from_frequency(freq: u32) -> Option<Self> {
+        match freq {
+            2312..=2484 => Some(Band::Band2GHz),
+            4900..=5885 => Some(Band::Band5GHz),
+            5935..=7125 => Some(Band::Band6GHz),
+            58320..=70200 => Some(Band::Band60GHz),
+            _ => None,
+        }
+    }
+
+    /// Band of a global operating class, as used by neighbor reports.
+    pub fn from_operating_class(operating_class: u8) -> Option<Self> {
+        match operating_class {
+            81..=84 => Some(Band::Band2GHz),
+            115..=130 => Some(Band::Band5GHz),
+            131..=137 => Some(Band::Band6GHz),
+            180..=185 => Some(Band::Band60GHz),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a centre frequency in MHz to an IEEE 802.11 channel number, following
+/// `ieee80211_freq_khz_to_channel` in the kernel.
+pub fn frequency_to_channel(freq: u32) -> Option<u32> {
+    match Band::from_frequency(freq)? {
+        Band::Band2GHz if freq == 2484 => Some(14),
+        Band::Band2GHz => freq.checked_sub(2407).map(|f| f / 5),
+        Band::Band5GHz if freq >= 5000 => Some((freq - 5000) / 5),
+        Band::Band5GHz => Some((freq - 4000) / 5),
+        Band::Band6GHz if freq == 5935 => Some(2),
+        Band::Band6GHz => Some((freq - 5950) / 5),
+        Band::Band60GHz => Some((freq - 56160) / 2160),
+        Band::BandS1GHz => None,
+    }
+}
+
+/// Converts an IEEE 802.11 channel number of a band to its centre frequency in MHz, following
+/// `ieee80211_channel_to_freq_khz` in the kernel.
+pub fn channel_to_frequency(channel: u32, band: Band) -> Option<u32> {
+    match band {
+        Band::Band2GHz if channel == 14 => Some(2484),
+        Band::Band2GHz if (1..14).contains(&channel) => Some(2407 + channel * 5),
+        Band::Band5GHz if (182..=196).contains(&channel) => Some(4000 + channel * 5),
+        Band::Band5GHz if (1..182).contains(&channel) => Some(5000 + channel * 5),
+        Band::Band6GHz if channel == 2 => Some(5935),
+        Band::Band6GHz if (1..=233).contains(&channel) => Some(5950 + channel * 5),
+        Band::Band60GHz if (1..7).contains(&channel) => Some(56160 + channel * 2160),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(channel: u32, band: Band, frequency: u32) {
+        assert_eq!(channel_to_frequency(channel, band), Some(frequency));
+        assert_eq!(frequency_to_channel(frequency), Some(channel));
+        assert_eq!(Band::from_frequency(frequency), Some(band));
+    }
+
+    #[test]
+    fn converts_2ghz_channels() {
+        round_trip(1, Band::Band2GHz, 2412);
+        round_trip(13, Band::Band2GHz, 2472);
+        round_trip(14, Band::Band2GHz, 2484);
+        assert_eq!(channel_to_frequency(15, Band::Band2GHz), None);
+    }
+
+    #[test]
+    fn converts_5ghz_channels() {
+        round_trip(36, Band::Band5GHz, 5180);
+        round_trip(177, Band::Band5GHz, 5885);
+    }
+
+    #[test]
+    fn converts_4_9ghz_channels() {
+        round_trip(182, Band::Band5GHz, 4910);
+        round_trip(196, Band::Band5GHz, 4980);
+        assert_eq!(channel_to_frequency(197, Band::Band5GHz), None);
+    }
+
+    #[test]
+    fn converts_6ghz_channels() {
+        round_trip(2, Band::Band6GHz, 5935);
+        round_trip(1, Band::Band6GHz, 5955);
+        round_trip(233, Band::Band6GHz, 7115);
+        assert_eq!(channel_to_frequency(234, Band::Band6GHz), None);
+    }
+
+    #[test]
+    fn converts_60ghz_channels() {
+        round_trip(1, Band::Band60GHz, 58320);
+        round_trip(6, Band::Band60GHz, 69120);
+        assert_eq!(channel_to_frequency(7, Band::Band60GHz), None);
+    }
+
+    #[test]
+    fn unknown_frequency_has_no_channel() {
+        assert_eq!(frequency_to_channel(2300), None);
+        assert_eq!(frequency_to_channel(5900), None);
+        assert_eq!(channel_to_frequency(1, Band::BandS1GHz), None);
+    }
+}
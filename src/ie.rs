@@ -0,0 +1,450 @@
+//! IEEE 802.11 information element parsing.
+//!
+//! [`Elements`] walks any buffer of concatenated elements, such as the beacon or probe response
+//! IEs reported by nl80211 or the IEs of an association request.
+
+use std::fmt;
+use std::iter::Peekable;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+pub const EID_SSID: u8 = 0;
+pub const EID_SUPPORTED_RATES: u8 = 1;
+pub const EID_DS_PARAMETER_SET: u8 = 3;
+pub const EID_TIM: u8 = 5;
+pub const EID_COUNTRY: u8 = 7;
+pub const EID_BSS_LOAD: u8 = 11;
+pub const EID_HT_CAPABILITIES: u8 = 45;
+pub const EID_RSN: u8 = 48;
+pub const EID_EXTENDED_SUPPORTED_RATES: u8 = 50;
+pub const EID_HT_OPERATION: u8 = 61;
+pub const EID_MULTIPLE_BSSID: u8 = 71;
+pub const EID_NONTRANSMITTED_BSSID_CAPABILITY: u8 = 83;
+pub const EID_MULTIPLE_BSSID_INDEX: u8 = 85;
+pub const EID_EXTENDED_CAPABILITIES: u8 = 127;
+pub const EID_VHT_CAPABILITIES: u8 = 191;
+pub const EID_VHT_OPERATION: u8 = 192;
+pub const EID_REDUCED_NEIGHBOR_REPORT: u8 = 201;
+pub const EID_VENDOR_SPECIFIC: u8 = 221;
+pub const EID_FRAGMENT: u8 = 242;
+pub const EID_EXTENSION: u8 = 255;
+
+pub const EID_EXT_HE_CAPABILITIES: u8 = 35;
+pub const EID_EXT_HE_OPERATION: u8 = 36;
+pub const EID_EXT_EHT_OPERATION: u8 = 106;
+pub const EID_EXT_MULTI_LINK: u8 = 107;
+pub const EID_EXT_EHT_CAPABILITIES: u8 = 108;
+
+const SSID_MAX_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element<'a> {
+    Ssid(&'a [u8]),
+    SupportedRates(&'a [u8]),
+    DsParameterSet {
+        channel: u8,
+    },
+    Tim(&'a [u8]),
+    Country(&'a [u8]),
+    BssLoad {
+        station_count: u16,
+        channel_utilization: u8,
+        available_admission_capacity: u16,
+    },
+    HtCapabilities(&'a [u8]),
+    Rsn(&'a [u8]),
+    ExtendedSupportedRates(&'a [u8]),
+    HtOperation(&'a [u8]),
+    MultipleBssid(&'a [u8]),
+    ExtendedCapabilities(&'a [u8]),
+    VhtCapabilities(&'a [u8]),
+    VhtOperation(&'a [u8]),
+    ReducedNeighborReport(&'a [u8]),
+    /// Vendor specific element, `data` starts right after the OUI.
+    VendorSpecific {
+        oui: [u8; 3],
+        data: &'a [u8],
+    },
+    HeCapabilities(&'a [u8]),
+    HeOperation(&'a [u8]),
+    EhtOperation(&'a [u8]),
+    MultiLink(&'a [u8]),
+    EhtCapabilities(&'a [u8]),
+    /// Element ID Extension element without a typed representation.
+    UnknownExtension {
+        id_ext: u8,
+        data: &'a [u8],
+    },
+    /// Element without a typed representation.
+    Unknown {
+        id: u8,
+        data: &'a [u8],
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseError {
+    /// The buffer ends in the middle of an element.
+    Truncated { offset: usize },
+    /// The element length is not valid for its ID.
+    InvalidLength {
+        id: u8,
+        id_ext: Option<u8>,
+        len: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Truncated { offset } => {
+                write!(f, "Truncated information element at offset {}", offset)
+            }
+            ParseError::InvalidLength {
+                id,
+                id_ext: Some(id_ext),
+                len,
+            } => write!(
+                f,
+                "Invalid length {} for information element {}/{}",
+                len, id, id_ext
+            ),
+            ParseError::InvalidLength { id, len, .. } => {
+                write!(f, "Invalid length {} for information element {}", len, id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Iterator over the information elements in a buffer.
+///
+/// Elements with a length invalid for their ID are reported as errors and skipped; a truncated
+/// element ends the iteration.
+#[derive(Debug, Clone)]
+pub struct Elements<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Elements<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Elements { data, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Result<Element<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = &self.data[self.offset..];
+        if remaining.is_empty() {
+            return None;
+        }
+
+        if remaining.len() < 2 || remaining.len() < 2 + remaining[1] as usize {
+            let offset = self.offset;
+            self.offset = self.data.len();
+            return Some(Err(ParseError::Truncated { offset }));
+        }
+
+        let id = remaining[0];
+        let len = remaining[1] as usize;
+        self.offset += 2 + len;
+
+        Some(parse_element(id, &remaining[2..2 + len]))
+    }
+}
+
+pub fn parse(data: &[u8]) -> Elements<'_> {
+    Elements::new(data)
+}
+
+/// Reassembles the body of an element longer than 255 bytes from the Fragment elements that
+/// follow it, as `cfg80211_defragment_element` does. `len` is the length field of the element,
+/// which for extension elements also counts the Element ID Extension.
+pub(crate) fn defragment(data: &[u8], len: usize, elements: &mut Peekable<Elements>) -> Vec<u8> {
+    let mut body = data.to_vec();
+    let mut last_len = len;
+
+    while last_len == 255 {
+        match elements.peek() {
+            Some(Ok(Element::Unknown {
+                id: EID_FRAGMENT,
+                data,
+            })) => {
+                body.extend_from_slice(data);
+                last_len = data.len();
+                elements.next();
+            }
+            _ => break,
+        }
+    }
+
+    body
+}
+
+fn parse_element(id: u8, data: &[u8]) -> Result<Element<'_>, ParseError> {
+    let invalid = || ParseError::InvalidLength {
+        id,
+        id_ext: None,
+        len: data.len(),
+    };
+
+    // Fixed-length elements may be extended by later amendments, so only the known prefix is
+    // read and any trailing bytes are ignored.
+    let element = match id {
+        EID_SSID if data.len() <= SSID_MAX_LEN => Element::Ssid(data),
+        EID_SUPPORTED_RATES if !data.is_empty() => Element::SupportedRates(data),
+        EID_DS_PARAMETER_SET if !data.is_empty() => Element::DsParameterSet { channel: data[0] },
+        EID_TIM if data.len() >= 4 => Element::Tim(data),
+        EID_COUNTRY if data.len() >= 3 => Element::Country(data),
+        EID_BSS_LOAD if data.len() >= 5 => Element::BssLoad {
+            station_count: LittleEndian::read_u16(&data[0..2]),
+            channel_utilization: data[2],
+            available_admission_capacity: LittleEndian::read_u16(&data[3..5]),
+        },
+        EID_HT_CAPABILITIES if data.len() >= 26 => Element::HtCapabilities(&data[..26]),
+        EID_RSN if data.len() >= 2 => Element::Rsn(data),
+        EID_EXTENDED_SUPPORTED_RATES if !data.is_empty() => Element::ExtendedSupportedRates(data),
+        EID_HT_OPERATION if data.len() >= 22 => Element::HtOperation(&data[..22]),
+        EID_MULTIPLE_BSSID if !data.is_empty() => Element::MultipleBssid(data),
+        EID_EXTENDED_CAPABILITIES => Element::ExtendedCapabilities(data),
+        EID_VHT_CAPABILITIES if data.len() >= 12 => Element::VhtCapabilities(&data[..12]),
+        EID_VHT_OPERATION if data.len() >= 5 => Element::VhtOperation(&data[..5]),
+        EID_REDUCED_NEIGHBOR_REPORT => Element::ReducedNeighborReport(data),
+        EID_VENDOR_SPECIFIC if data.len() >= 3 => Element::VendorSpecific {
+            oui: [data[0], data[1], data[2]],
+            data: &data[3..],
+        },
+        EID_EXTENSION if !data.is_empty() => return parse_extension(data[0], &data[1..]),
+        EID_SSID
+        | EID_SUPPORTED_RATES
+        | EID_DS_PARAMETER_SET
+        | EID_TIM
+        | EID_COUNTRY
+        | EID_BSS_LOAD
+        | EID_HT_CAPABILITIES
+        | EID_RSN
+        | EID_EXTENDED_SUPPORTED_RATES
+        | EID_HT_OPERATION
+        | EID_MULTIPLE_BSSID
+        | EID_VHT_CAPABILITIES
+        | EID_VHT_OPERATION
+        | EID_VENDOR_SPECIFIC
+        | EID_EXTENSION => return Err(invalid()),
+        _ => Element::Unknown { id, data },
+    };
+
+    Ok(element)
+}
+
+fn parse_extension(id_ext: u8, data: &[u8]) -> Result<Element<'_>, ParseError> {
+    let invalid = || ParseError::InvalidLength {
+        id: EID_EXTENSION,
+        id_ext: Some(id_ext),
+        len: data.len(),
+    };
+
+    let element = match id_ext {
+        EID_EXT_HE_CAPABILITIES if data.len() >= 21 => Element::HeCapabilities(data),
+        EID_EXT_HE_OPERATION if data.len() >= 6 => Element::HeOperation(data),
+        EID_EXT_EHT_OPERATION if data.len() >= 5 => Element::EhtOperation(data),
+        EID_EXT_MULTI_LINK if data.len() >= 3 => Element::MultiLink(data),
+        EID_EXT_EHT_CAPABILITIES if data.len() >= 11 => Element::EhtCapabilities(data),
+        EID_EXT_HE_CAPABILITIES
+        | EID_EXT_HE_OPERATION
+        | EID_EXT_EHT_OPERATION
+        | EID_EXT_MULTI_LINK
+        | EID_EXT_EHT_CAPABILITIES => return Err(invalid()),
+        _ => Element::UnknownExtension { id_ext, data },
+    };
+
+    Ok(element)
+}
+
+/// Little-endian cursor over the body of an element.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(LittleEndian::read_u16)
+    }
+
+    pub(crate) fn selector(&mut self) -> Option<([u8; 3], u8)> {
+        let bytes = self.bytes(4)?;
+        Some(([bytes[0], bytes[1], bytes[2]], bytes[3]))
+    }
+
+    pub(crate) fn selectors<T>(&mut self, f: fn([u8; 3], u8) -> T) -> Option<Vec<T>> {
+        let count = self.u16()?;
+        (0..count)
+            .map(|_| self.selector().map(|(oui, suite_type)| f(oui, suite_type)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_beacon_elements() {
+        let data = [
+            0x00, 0x04, b'h', b'o', b'm', b'e', // SSID
+            0x01, 0x02, 0x82, 0x84, // Supported Rates
+            0x03, 0x01, 0x06, // DS Parameter Set
+            0x0b, 0x05, 0x03, 0x00, 0x40, 0x00, 0x00, // BSS Load
+            0xdd, 0x05, 0x00, 0x50, 0xf2, 0x02, 0x01, // WMM vendor element
+            0xff, 0x02, 0x63, 0xaa, // unknown extension
+        ];
+
+        let elements: Vec<_> = parse(&data).collect();
+
+        assert_eq!(
+            elements,
+            vec![
+                Ok(Element::Ssid(b"home")),
+                Ok(Element::SupportedRates(&[0x82, 0x84])),
+                Ok(Element::DsParameterSet { channel: 6 }),
+                Ok(Element::BssLoad {
+                    station_count: 3,
+                    channel_utilization: 0x40,
+                    available_admission_capacity: 0,
+                }),
+                Ok(Element::VendorSpecific {
+                    oui: [0x00, 0x50, 0xf2],
+                    data: &[0x02, 0x01],
+                }),
+                Ok(Element::UnknownExtension {
+                    id_ext: 0x63,
+                    data: &[0xaa],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_element_ends_iteration() {
+        let data = [0x00, 0x01, b'a', 0x01, 0x04, 0x82];
+
+        let elements: Vec<_> = parse(&data).collect();
+
+        assert_eq!(
+            elements,
+            vec![
+                Ok(Element::Ssid(b"a")),
+                Err(ParseError::Truncated { offset: 3 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn lone_id_byte_is_truncated() {
+        let elements: Vec<_> = parse(&[0x00]).collect();
+
+        assert_eq!(elements, vec![Err(ParseError::Truncated { offset: 0 })]);
+    }
+
+    #[test]
+    fn reads_known_prefix_of_padded_element() {
+        let mut ht_capabilities = [0; 28];
+        ht_capabilities[3] = 0xff;
+        ht_capabilities[26..].copy_from_slice(&[0xaa, 0xbb]);
+        let mut data = vec![EID_HT_CAPABILITIES, 28];
+        data.extend_from_slice(&ht_capabilities);
+        data.extend_from_slice(&[0x03, 0x02, 0x0b, 0x00]);
+
+        let elements: Vec<_> = parse(&data).collect();
+
+        assert_eq!(
+            elements,
+            vec![
+                Ok(Element::HtCapabilities(&ht_capabilities[..26])),
+                Ok(Element::DsParameterSet { channel: 11 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_length_is_skipped() {
+        let mut data = vec![0x00, 33];
+        data.extend_from_slice(&[b'x'; 33]);
+        data.extend_from_slice(&[0x03, 0x00, 0x03, 0x01, 0x0b]);
+
+        let elements: Vec<_> = parse(&data).collect();
+
+        assert_eq!(
+            elements,
+            vec![
+                Err(ParseError::InvalidLength {
+                    id: EID_SSID,
+                    id_ext: None,
+                    len: 33,
+                }),
+                Err(ParseError::InvalidLength {
+                    id: EID_DS_PARAMETER_SET,
+                    id_ext: None,
+                    len: 0,
+                }),
+                Ok(Element::DsParameterSet { channel: 11 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_extension_length() {
+        let elements: Vec<_> = parse(&[0xff, 0x03, EID_EXT_HE_OPERATION, 0x00, 0x00]).collect();
+
+        assert_eq!(
+            elements,
+            vec![Err(ParseError::InvalidLength {
+                id: EID_EXTENSION,
+                id_ext: Some(EID_EXT_HE_OPERATION),
+                len: 2,
+            })]
+        );
+    }
+
+    #[test]
+    fn empty_extension_element_is_invalid() {
+        let elements: Vec<_> = parse(&[0xff, 0x00]).collect();
+
+        assert_eq!(
+            elements,
+            vec![Err(ParseError::InvalidLength {
+                id: EID_EXTENSION,
+                id_ext: None,
+                len: 0,
+            })]
+        );
+    }
+
+    #[test]
+    fn reader_reads_little_endian() {
+        let mut reader = Reader(&[0x01, 0x00, 0x00, 0x0f, 0xac, 0x04]);
+
+        assert_eq!(reader.u16(), Some(1));
+        assert_eq!(reader.selector(), Some(([0x00, 0x0f, 0xac], 4)));
+        assert!(reader.is_empty());
+        assert_eq!(reader.u16(), None);
+    }
+}
@@ -6,8 +6,11 @@ use macaddr::MacAddr6;
 
 use neli::genl::Genlmsghdr;
 
+use crate::capabilities::ChannelWidth;
 use crate::consts;
 use crate::enums::{Nl80211Attr, Nl80211Cmd};
+use crate::error::Error;
+use crate::frequency::frequency_to_channel;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InterfaceType {
@@ -47,24 +50,68 @@ impl From<::std::os::raw::c_uint> for InterfaceType {
     }
 }
 
-#[allow(dead_code)]
+/// Identifies an interface by netdev name, netdev index or wireless device ID.
+///
+/// Interfaces without a netdev, such as P2P devices, can only be identified by their wdev.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InterfaceId {
+    Name(String),
+    Index(u32),
+    Wdev(u64),
+}
+
+impl From<&str> for InterfaceId {
+    fn from(name: &str) -> Self {
+        InterfaceId::Name(name.to_owned())
+    }
+}
+
+impl From<String> for InterfaceId {
+    fn from(name: String) -> Self {
+        InterfaceId::Name(name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Interface {
-    pub name: String,
-    pub index: u32,
+    pub name: Option<String>,
+    pub index: Option<u32>,
     pub iftype: InterfaceType,
     pub wiphy: u32,
     pub wdev: u64,
     pub mac_address: MacAddr6,
+    /// SSID of the current connection, decoded for display.
+    pub ssid: Option<String>,
+    /// SSID of the current connection exactly as advertised by the network.
+    pub ssid_bytes: Option<Vec<u8>>,
+    /// Operating frequency in MHz.
+    pub frequency: Option<u32>,
+    pub channel: Option<u32>,
+    pub channel_width: Option<ChannelWidth>,
+    pub center_frequency1: Option<u32>,
+    pub center_frequency2: Option<u32>,
+    pub tx_power_dbm: Option<i32>,
+    /// Set when the interface uses four-address (WDS) frames.
+    pub four_addr: bool,
+}
+
+impl Interface {
+    pub fn matches(&self, id: &InterfaceId) -> bool {
+        match id {
+            InterfaceId::Name(name) => self.name.as_ref() == Some(name),
+            InterfaceId::Index(index) => self.index == Some(*index),
+            InterfaceId::Wdev(wdev) => self.wdev == *wdev,
+        }
+    }
 }
 
 impl TryFrom<&Genlmsghdr<Nl80211Cmd, Nl80211Attr>> for Interface {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(payload: &Genlmsghdr<Nl80211Cmd, Nl80211Attr>) -> Result<Self, Self::Error> {
         let attrs = payload.get_attr_handle();
-        let name = attrs.get_attr_payload_as_with_len(Nl80211Attr::Ifname)?;
-        let index = attrs.get_attr_payload_as(Nl80211Attr::Ifindex)?;
+        let name = attrs.get_attr_payload_as_with_len(Nl80211Attr::Ifname).ok();
+        let index = attrs.get_attr_payload_as(Nl80211Attr::Ifindex).ok();
         let iftype = attrs
             .get_attr_payload_as::<u32>(Nl80211Attr::Iftype)?
             .into();
@@ -74,6 +121,29 @@ impl TryFrom<&Genlmsghdr<Nl80211Cmd, Nl80211Attr>> for Interface {
             .get_attr_payload_as_with_len::<&[u8]>(Nl80211Attr::Mac)?
             .try_into()?;
         let mac_address = mac_bytes.into();
+        let ssid_bytes = attrs
+            .get_attr_payload_as_with_len::<&[u8]>(Nl80211Attr::Ssid)
+            .ok()
+            .map(<[u8]>::to_vec);
+        let ssid = ssid_bytes
+            .as_ref()
+            .map(|ssid| String::from_utf8_lossy(ssid).into_owned());
+        let frequency = attrs.get_attr_payload_as(Nl80211Attr::WiphyFreq).ok();
+        let channel = frequency.and_then(frequency_to_channel);
+        let channel_width = attrs
+            .get_attr_payload_as::<u32>(Nl80211Attr::ChannelWidth)
+            .ok()
+            .and_then(ChannelWidth::from_nl80211);
+        let center_frequency1 = attrs.get_attr_payload_as(Nl80211Attr::CenterFreq1).ok();
+        let center_frequency2 = attrs.get_attr_payload_as(Nl80211Attr::CenterFreq2).ok();
+        let tx_power_dbm = attrs
+            .get_attr_payload_as::<i32>(Nl80211Attr::WiphyTxPowerLevel)
+            .ok()
+            .map(|tx_power_mbm| tx_power_mbm / 100);
+        let four_addr = attrs
+            .get_attr_payload_as::<u8>(Nl80211Attr::FourAddr)
+            .map(|four_addr| four_addr != 0)
+            .unwrap_or_default();
         Ok(Interface {
             name,
             index,
@@ -81,6 +151,15 @@ impl TryFrom<&Genlmsghdr<Nl80211Cmd, Nl80211Attr>> for Interface {
             wiphy,
             wdev,
             mac_address,
+            ssid,
+            ssid_bytes,
+            frequency,
+            channel,
+            channel_width,
+            center_frequency1,
+            center_frequency2,
+            tx_power_dbm,
+            four_addr,
         })
     }
 }
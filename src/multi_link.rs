@@ -0,0 +1,354 @@
+use macaddr::MacAddr6;
+
+use crate::ie::{self, Element, Reader};
+use crate::neighbor::Neighbor;
+use crate::station::Station;
+
+const MULTI_LINK_TYPE_BASIC: u16 = 0;
+
+const BASIC_PRESENCE_LINK_ID: u16 = 1 << 4;
+const BASIC_PRESENCE_BSS_PARAMS_CHANGE_COUNT: u16 = 1 << 5;
+const BASIC_PRESENCE_MEDIUM_SYNC_DELAY: u16 = 1 << 6;
+const BASIC_PRESENCE_EML_CAPABILITIES: u16 = 1 << 7;
+const BASIC_PRESENCE_MLD_CAPABILITIES: u16 = 1 << 8;
+const BASIC_PRESENCE_MLD_ID: u16 = 1 << 9;
+
+const LINK_INFO_PER_STA_PROFILE: u8 = 0;
+const LINK_INFO_FRAGMENT: u8 = 254;
+
+const STA_CONTROL_LINK_ID_MASK: u16 = 0xf;
+const STA_CONTROL_COMPLETE_PROFILE: u16 = 1 << 4;
+const STA_CONTROL_MAC_ADDRESS_PRESENT: u16 = 1 << 5;
+
+/// Basic Multi-Link element, advertising the AP MLD a Wi-Fi 7 BSS is affiliated with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MultiLink {
+    pub mld_address: MacAddr6,
+    /// Link ID of the reporting BSS within the AP MLD.
+    pub link_id: Option<u8>,
+    pub mld_id: Option<u8>,
+    /// Other links of the AP MLD described by the element.
+    pub links: Vec<MultiLinkProfile>,
+}
+
+/// Per-STA profile of a Basic Multi-Link element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MultiLinkProfile {
+    pub link_id: u8,
+    pub mac_address: Option<MacAddr6>,
+    /// The profile carries all elements of the link, as in probe responses for the MLD.
+    pub complete: bool,
+}
+
+impl MultiLink {
+    /// Parses the body of a Multi-Link element, after the Element ID Extension. Only the Basic
+    /// variant is decoded. A body longer than 254 bytes must already be reassembled from the
+    /// Fragment elements that follow the element, while fragmented per-STA profiles are
+    /// reassembled here.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+
+        let control = reader.u16()?;
+        if control & 0x7 != MULTI_LINK_TYPE_BASIC {
+            return None;
+        }
+
+        let common_len = *reader.bytes(1)?.first()? as usize;
+        let mut common = Reader(reader.bytes(common_len.checked_sub(1)?)?);
+
+        let mld_address = mac_address(&mut common)?;
+        let link_id = match control & BASIC_PRESENCE_LINK_ID {
+            0 => None,
+            _ => Some(common.bytes(1)?[0] & 0xf),
+        };
+        for (presence, len) in [
+            (BASIC_PRESENCE_BSS_PARAMS_CHANGE_COUNT, 1),
+            (BASIC_PRESENCE_MEDIUM_SYNC_DELAY, 2),
+            (BASIC_PRESENCE_EML_CAPABILITIES, 2),
+            (BASIC_PRESENCE_MLD_CAPABILITIES, 2),
+        ] {
+            if control & presence != 0 {
+                common.bytes(len)?;
+            }
+        }
+        let mld_id = match control & BASIC_PRESENCE_MLD_ID {
+            0 => None,
+            _ => Some(common.bytes(1)?[0]),
+        };
+
+        let mut links = Vec::new();
+        while !reader.is_empty() {
+            let header = reader.bytes(2)?;
+            let mut subelement = reader.bytes(header[1] as usize)?.to_vec();
+
+            // Subelements longer than 255 bytes continue in Fragment subelements.
+            let mut last_len = header[1];
+            while last_len == 255 && reader.peek() == Some(LINK_INFO_FRAGMENT) {
+                let fragment = reader.bytes(2)?;
+                subelement.extend_from_slice(reader.bytes(fragment[1] as usize)?);
+                last_len = fragment[1];
+            }
+
+            if header[0] != LINK_INFO_PER_STA_PROFILE {
+                continue;
+            }
+            if let Some(profile) = parse_per_sta_profile(&subelement) {
+                links.push(profile);
+            }
+        }
+
+        Some(MultiLink {
+            mld_address,
+            link_id,
+            mld_id,
+            links,
+        })
+    }
+}
+
+fn parse_per_sta_profile(data: &[u8]) -> Option<MultiLinkProfile> {
+    let mut reader = Reader(data);
+
+    let control = reader.u16()?;
+    let sta_info_len = *reader.bytes(1)?.first()? as usize;
+    let mut sta_info = Reader(reader.bytes(sta_info_len.checked_sub(1)?)?);
+    let mac_address = match control & STA_CONTROL_MAC_ADDRESS_PRESENT {
+        0 => None,
+        _ => Some(mac_address(&mut sta_info)?),
+    };
+
+    Some(MultiLinkProfile {
+        link_id: (control & STA_CONTROL_LINK_ID_MASK) as u8,
+        mac_address,
+        complete: control & STA_CONTROL_COMPLETE_PROFILE != 0,
+    })
+}
+
+fn mac_address(reader: &mut Reader) -> Option<MacAddr6> {
+    let b = reader.bytes(6)?;
+    Some(MacAddr6::new(b[0], b[1], b[2], b[3], b[4], b[5]))
+}
+
+/// AP MLD and the links of it found by a scan.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApMld {
+    pub mld_address: MacAddr6,
+    /// Affiliated BSSes that were heard, each with its own band and channel.
+    pub links: Vec<Station>,
+    /// Affiliated BSSes only known from the Reduced Neighbor Reports of the heard links.
+    pub reported_links: Vec<Neighbor>,
+}
+
+impl Station {
+    pub fn multi_link(&self) -> Option<MultiLink> {
+        extract_multi_link(&self.ies)
+    }
+}
+
+pub(crate) fn extract_multi_link(ies: &[u8]) -> Option<MultiLink> {
+    let mut elements = ie::parse(ies).peekable();
+
+    while let Some(element) = elements.next() {
+        if let Ok(Element::MultiLink(data)) = element {
+            // The element length also counts the Element ID Extension.
+            let body = ie::defragment(data, data.len() + 1, &mut elements);
+            if let Some(multi_link) = MultiLink::parse(&body) {
+                return Some(multi_link);
+            }
+        }
+    }
+
+    None
+}
+
+/// Groups the scan results of Wi-Fi 7 BSSes by the AP MLD they are affiliated with. BSSes that
+/// are not part of an MLD are left out.
+pub fn group_ap_mlds(stations: &[Station]) -> Vec<ApMld> {
+    let mut mlds: Vec<ApMld> = Vec::new();
+
+    for station in stations {
+        let mld_address = match station.mld_address {
+            Some(mld_address) => mld_address,
+            None => continue,
+        };
+
+        match mlds.iter_mut().find(|mld| mld.mld_address == mld_address) {
+            Some(mld) => mld.links.push(station.clone()),
+            None => mlds.push(ApMld {
+                mld_address,
+                links: vec![station.clone()],
+                reported_links: Vec::new(),
+            }),
+        }
+    }
+
+    for mld in &mut mlds {
+        let mut reported_links: Vec<Neighbor> = Vec::new();
+        let neighbors = mld.links.iter().flat_map(Station::neighbors);
+        for neighbor in neighbors {
+            // MLD ID 0 refers to the AP MLD of the reporting BSS.
+            let same_mld = neighbor.mld.map(|mld| mld.mld_id == 0).unwrap_or_default();
+            let heard = mld
+                .links
+                .iter()
+                .any(|link| Some(link.bssid) == neighbor.bssid);
+            let known = reported_links
+                .iter()
+                .any(|link| link.bssid == neighbor.bssid);
+            if same_mld && !heard && !known {
+                reported_links.push(neighbor);
+            }
+        }
+        mld.reported_links = reported_links;
+    }
+
+    mlds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighbor::tests::{rnr_element, tbtt_info};
+    use crate::station::tests::station;
+
+    const MLD_ADDRESS: [u8; 6] = [0x02, 0xaa, 0x00, 0x00, 0x00, 0x01];
+    const LINK_ADDRESS: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x02];
+
+    /// Encodes an element or subelement, continuing it in fragments if it exceeds 255 bytes.
+    fn fragmented(id: u8, fragment_id: u8, body: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for (i, chunk) in body.chunks(255).enumerate() {
+            encoded.push(if i == 0 { id } else { fragment_id });
+            encoded.push(chunk.len() as u8);
+            encoded.extend_from_slice(chunk);
+        }
+        encoded
+    }
+
+    fn multi_link_element(profiles: &[Vec<u8>]) -> Vec<u8> {
+        let control = BASIC_PRESENCE_LINK_ID
+            | BASIC_PRESENCE_BSS_PARAMS_CHANGE_COUNT
+            | BASIC_PRESENCE_MLD_CAPABILITIES;
+        let mut body = vec![ie::EID_EXT_MULTI_LINK];
+        body.extend_from_slice(&control.to_le_bytes());
+        body.push(11);
+        body.extend_from_slice(&MLD_ADDRESS);
+        body.extend_from_slice(&[0x01, 0x05, 0x00, 0x00]);
+        for profile in profiles {
+            body.extend(fragmented(
+                LINK_INFO_PER_STA_PROFILE,
+                LINK_INFO_FRAGMENT,
+                profile,
+            ));
+        }
+        fragmented(ie::EID_EXTENSION, ie::EID_FRAGMENT, &body)
+    }
+
+    fn per_sta_profile(link_id: u16, elements: &[u8]) -> Vec<u8> {
+        let control = link_id | STA_CONTROL_COMPLETE_PROFILE | STA_CONTROL_MAC_ADDRESS_PRESENT;
+        let mut profile = control.to_le_bytes().to_vec();
+        profile.push(7);
+        profile.extend_from_slice(&LINK_ADDRESS);
+        profile.extend_from_slice(&[0x11, 0x04]);
+        profile.extend_from_slice(elements);
+        profile
+    }
+
+    #[test]
+    fn parses_basic_multi_link() {
+        let ies = multi_link_element(&[
+            per_sta_profile(2, &[ie::EID_SSID, 0x04, b'h', b'o', b'm', b'e']),
+            vec![0x03, 0x00, 0x01],
+        ]);
+
+        assert_eq!(
+            extract_multi_link(&ies),
+            Some(MultiLink {
+                mld_address: MLD_ADDRESS.into(),
+                link_id: Some(1),
+                mld_id: None,
+                links: vec![
+                    MultiLinkProfile {
+                        link_id: 2,
+                        mac_address: Some(LINK_ADDRESS.into()),
+                        complete: true,
+                    },
+                    MultiLinkProfile {
+                        link_id: 3,
+                        mac_address: None,
+                        complete: false,
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_other_multi_link_types() {
+        // Probe request variant.
+        assert_eq!(MultiLink::parse(&[0x01, 0x00, 0x01]), None);
+    }
+
+    #[test]
+    fn truncated_common_info_is_invalid() {
+        let mut body = BASIC_PRESENCE_LINK_ID.to_le_bytes().to_vec();
+        body.push(8);
+        body.extend_from_slice(&MLD_ADDRESS);
+
+        assert_eq!(MultiLink::parse(&body), None);
+    }
+
+    #[test]
+    fn defragments_element_and_per_sta_profile() {
+        let mut vendor = vec![ie::EID_VENDOR_SPECIFIC, 0xfa];
+        vendor.extend_from_slice(&[0x5a; 0xfa]);
+        let mut ies = multi_link_element(&[
+            per_sta_profile(2, &vendor),
+            per_sta_profile(3, &[ie::EID_SSID, 0x00]),
+        ]);
+        ies.extend_from_slice(&[ie::EID_DS_PARAMETER_SET, 0x01, 0x06]);
+
+        assert!(ies.contains(&ie::EID_FRAGMENT));
+        let multi_link = extract_multi_link(&ies).unwrap();
+
+        assert_eq!(multi_link.mld_address, MacAddr6::from(MLD_ADDRESS));
+        let link_ids: Vec<u8> = multi_link.links.iter().map(|link| link.link_id).collect();
+        assert_eq!(link_ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn groups_links_by_ap_mld() {
+        let bssid = |last| MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, last);
+        let neighbor = |last, mld_id, link_id| {
+            tbtt_info(&[
+                bssid(last).as_bytes(),
+                &[0x00; 4],
+                &[0x00, 0x00, mld_id, link_id, 0x00],
+            ])
+        };
+
+        let reported = [
+            neighbor(0x02, 0, 1),
+            neighbor(0x03, 0, 2),
+            neighbor(0x04, 1, 0),
+            neighbor(0x03, 0, 2),
+        ];
+        let mut first = station(bssid(0x01), &rnr_element(131, 5, &reported));
+        first.mld_address = Some(MLD_ADDRESS.into());
+        let mut second = station(bssid(0x02), &[]);
+        second.mld_address = Some(MLD_ADDRESS.into());
+        let legacy = station(bssid(0x10), &[]);
+        let mut other = station(bssid(0x20), &[]);
+        other.mld_address = Some(LINK_ADDRESS.into());
+
+        let mlds = group_ap_mlds(&[first.clone(), legacy, other.clone(), second.clone()]);
+
+        assert_eq!(mlds.len(), 2);
+        assert_eq!(mlds[0].mld_address, MacAddr6::from(MLD_ADDRESS));
+        assert_eq!(mlds[0].links, vec![first, second]);
+        let reported: Vec<_> = mlds[0].reported_links.iter().map(|n| n.bssid).collect();
+        assert_eq!(reported, vec![Some(bssid(0x03))]);
+        assert_eq!(mlds[1].links, vec![other]);
+        assert!(mlds[1].reported_links.is_empty());
+    }
+}
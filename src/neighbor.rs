@@ -0,0 +1,554 @@
+use std::convert::TryInto;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use macaddr::MacAddr6;
+
+use crate::frequency::{channel_to_frequency, Band};
+use crate::ie::{self, Element, Reader};
+use crate::station::{BssCapability, Station};
+
+const MULTIPLE_BSSID_SUBELEMENT_PROFILE: u8 = 0;
+
+const TBTT_INFO_FIELD_TYPE_NEIGHBOR: u16 = 0;
+
+const BSS_PARAMS_SAME_SSID: u8 = 1 << 1;
+const BSS_PARAMS_MULTIPLE_BSSID: u8 = 1 << 2;
+const BSS_PARAMS_TRANSMITTED_BSSID: u8 = 1 << 3;
+const BSS_PARAMS_COLOCATED_AP: u8 = 1 << 6;
+
+/// AP listed in a Reduced Neighbor Report element, which 2.4/5 GHz APs use to advertise their
+/// co-located 6 GHz BSSes, whether or not these were heard directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Neighbor {
+    pub operating_class: u8,
+    pub channel: u8,
+    pub band: Option<Band>,
+    /// Centre frequency in MHz.
+    pub frequency: Option<u32>,
+    pub bssid: Option<MacAddr6>,
+    /// CRC32 of the SSID, used by 6 GHz probing instead of the full SSID.
+    pub short_ssid: Option<u32>,
+    /// The neighbor has the same SSID as the reporting BSS.
+    pub same_ssid: bool,
+    /// The neighbor is part of a Multiple BSSID set.
+    pub multiple_bssid: bool,
+    /// The neighbor is the transmitted BSS of its Multiple BSSID set.
+    pub transmitted_bssid: bool,
+    /// The neighbor is run by the same physical AP as the reporting BSS.
+    pub colocated_ap: bool,
+    /// Affiliation of the neighbor with an AP MLD.
+    pub mld: Option<NeighborMld>,
+}
+
+/// MLD parameters of a Reduced Neighbor Report entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NeighborMld {
+    /// 0 for the AP MLD of the reporting BSS, otherwise the index of the BSS of another AP MLD
+    /// within its Multiple BSSID set.
+    pub mld_id: u8,
+    pub link_id: u8,
+}
+
+/// Virtual BSS advertised in the Multiple BSSID element of the transmitted BSS, which answers
+/// and beacons on its behalf.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NontransmittedBss {
+    pub bssid: MacAddr6,
+    pub ssid: Vec<u8>,
+    pub bssid_index: u8,
+    pub bss_capability: Option<BssCapability>,
+}
+
+/// Physical AP and all the BSSes it runs, across bands and Multiple BSSID sets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessPoint {
+    pub bsses: Vec<Station>,
+}
+
+impl Station {
+    /// Neighbors listed in the Reduced Neighbor Report elements of the BSS.
+    pub fn neighbors(&self) -> Vec<Neighbor> {
+        self.elements()
+            .filter_map(Result::ok)
+            .filter_map(|element| match element {
+                Element::ReducedNeighborReport(data) => Some(parse_reduced_neighbor_report(data)),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Virtual BSSes listed in the Multiple BSSID elements of the BSS.
+    pub fn nontransmitted_bsses(&self) -> Vec<NontransmittedBss> {
+        self.elements()
+            .filter_map(Result::ok)
+            .filter_map(|element| match element {
+                Element::MultipleBssid(data) => Some(parse_multiple_bssid(self.bssid, data)),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// Groups scan results into physical APs: virtual BSSes join the transmitted BSS named by their
+/// parent BSSID, and BSSes listed as co-located in a Reduced Neighbor Report join the reporting
+/// BSS.
+pub fn group_access_points(stations: &[Station]) -> Vec<AccessPoint> {
+    let mut groups: Vec<usize> = (0..stations.len()).collect();
+
+    let position = |bssid: MacAddr6| stations.iter().position(|s| s.bssid == bssid);
+
+    for (i, station) in stations.iter().enumerate() {
+        if let Some(parent) = station.parent_bssid.and_then(position) {
+            union(&mut groups, i, parent);
+        }
+
+        for neighbor in station.neighbors() {
+            if !neighbor.colocated_ap {
+                continue;
+            }
+            if let Some(other) = neighbor.bssid.and_then(position) {
+                union(&mut groups, i, other);
+            }
+        }
+    }
+
+    let mut access_points: Vec<(usize, AccessPoint)> = Vec::new();
+    for (i, station) in stations.iter().enumerate() {
+        let root = find(&mut groups, i);
+        match access_points.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, access_point)) => access_point.bsses.push(station.clone()),
+            None => access_points.push((
+                root,
+                AccessPoint {
+                    bsses: vec![station.clone()],
+                },
+            )),
+        }
+    }
+
+    access_points
+        .into_iter()
+        .map(|(_, access_point)| access_point)
+        .collect()
+}
+
+fn find(groups: &mut [usize], mut i: usize) -> usize {
+    while groups[i] != i {
+        groups[i] = groups[groups[i]];
+        i = groups[i];
+    }
+    i
+}
+
+fn union(groups: &mut [usize], a: usize, b: usize) {
+    let a = find(groups, a);
+    let b = find(groups, b);
+    groups[a.max(b)] = a.min(b);
+}
+
+fn parse_reduced_neighbor_report(data: &[u8]) -> Vec<Neighbor> {
+    let mut neighbors = Vec::new();
+    let mut reader = Reader(data);
+
+    while !reader.is_empty() {
+        let header = match reader.u16() {
+            Some(header) => header,
+            None => break,
+        };
+        let field_type = header & 0x3;
+        let count = ((header >> 4) & 0xf) as usize + 1;
+        let len = (header >> 8) as usize;
+
+        let (operating_class, channel) = match reader.bytes(2) {
+            Some(channel) => (channel[0], channel[1]),
+            None => break,
+        };
+        let infos = match reader.bytes(count * len) {
+            Some(infos) => infos,
+            None => break,
+        };
+
+        if field_type != TBTT_INFO_FIELD_TYPE_NEIGHBOR || len == 0 {
+            continue;
+        }
+
+        let band = Band::from_operating_class(operating_class);
+        let frequency = band.and_then(|band| channel_to_frequency(channel.into(), band));
+
+        for info in infos.chunks_exact(len) {
+            if let Some((bssid, short_ssid, bss_params, mld)) = parse_tbtt_info(info) {
+                let bss_params = bss_params.unwrap_or_default();
+                neighbors.push(Neighbor {
+                    operating_class,
+                    channel,
+                    band,
+                    frequency,
+                    bssid,
+                    short_ssid,
+                    same_ssid: bss_params & BSS_PARAMS_SAME_SSID != 0,
+                    multiple_bssid: bss_params & BSS_PARAMS_MULTIPLE_BSSID != 0,
+                    transmitted_bssid: bss_params & BSS_PARAMS_TRANSMITTED_BSSID != 0,
+                    colocated_ap: bss_params & BSS_PARAMS_COLOCATED_AP != 0,
+                    mld,
+                });
+            }
+        }
+    }
+
+    neighbors
+}
+
+type TbttInfo = (
+    Option<MacAddr6>,
+    Option<u32>,
+    Option<u8>,
+    Option<NeighborMld>,
+);
+
+/// Splits a TBTT Information field, whose layout depends on its length, into its BSSID, short
+/// SSID, BSS parameters and MLD parameters. All layouts start with the TBTT offset.
+fn parse_tbtt_info(info: &[u8]) -> Option<TbttInfo> {
+    let bssid = |offset: usize| -> Option<MacAddr6> {
+        let bytes: [u8; 6] = info.get(offset..offset + 6)?.try_into().ok()?;
+        Some(bytes.into())
+    };
+    let short_ssid = |offset: usize| info.get(offset..offset + 4).map(LittleEndian::read_u32);
+
+    let mld = || NeighborMld {
+        mld_id: info[13],
+        link_id: info[14] & 0xf,
+    };
+
+    let fields = match info.len() {
+        1 => (None, None, None, None),
+        2 => (None, None, Some(info[1]), None),
+        5 => (None, short_ssid(1), None, None),
+        6 => (None, short_ssid(1), Some(info[5]), None),
+        7 => (bssid(1), None, None, None),
+        8 | 9 => (bssid(1), None, Some(info[7]), None),
+        11 => (bssid(1), short_ssid(7), None, None),
+        12 | 13 => (bssid(1), short_ssid(7), Some(info[11]), None),
+        16.. => (bssid(1), short_ssid(7), Some(info[11]), Some(mld())),
+        _ => return None,
+    };
+
+    Some(fields)
+}
+
+fn parse_multiple_bssid(transmitter: MacAddr6, data: &[u8]) -> Vec<NontransmittedBss> {
+    let mut bsses = Vec::new();
+
+    let (max_bssid_indicator, mut subelements) = match data.split_first() {
+        Some((&indicator, rest)) => (indicator, Reader(rest)),
+        None => return bsses,
+    };
+
+    while !subelements.is_empty() {
+        let header = match subelements.bytes(2) {
+            Some(header) => header,
+            None => break,
+        };
+        let profile = match subelements.bytes(header[1] as usize) {
+            Some(profile) => profile,
+            None => break,
+        };
+
+        if header[0] != MULTIPLE_BSSID_SUBELEMENT_PROFILE {
+            continue;
+        }
+
+        let mut ssid = Vec::new();
+        let mut bssid_index = None;
+        let mut bss_capability = None;
+        for element in ie::parse(profile).filter_map(Result::ok) {
+            match element {
+                Element::Ssid(data) => ssid = data.to_vec(),
+                Element::Unknown {
+                    id: ie::EID_MULTIPLE_BSSID_INDEX,
+                    data,
+                } => bssid_index = data.first().copied(),
+                Element::Unknown {
+                    id: ie::EID_NONTRANSMITTED_BSSID_CAPABILITY,
+                    data,
+                } if data.len() == 2 => {
+                    bss_capability = Some(BssCapability(LittleEndian::read_u16(data)));
+                }
+                _ => {}
+            }
+        }
+
+        // Profiles split over several elements only carry the index in their first part.
+        if let Some(bssid_index) = bssid_index {
+            bsses.push(NontransmittedBss {
+                bssid: nontransmitted_bssid(transmitter, max_bssid_indicator, bssid_index),
+                ssid,
+                bssid_index,
+                bss_capability,
+            });
+        }
+    }
+
+    bsses
+}
+
+/// Derives the BSSID of a virtual BSS from the transmitted BSSID, following
+/// `cfg80211_gen_new_bssid` in the kernel.
+fn nontransmitted_bssid(transmitter: MacAddr6, max_bssid_indicator: u8, index: u8) -> MacAddr6 {
+    let mut bytes = [0; 8];
+    bytes[2..].copy_from_slice(transmitter.as_bytes());
+    let transmitter = u64::from_be_bytes(bytes);
+
+    let mask = (1u64 << max_bssid_indicator.min(47)) - 1;
+    let bssid = (transmitter & !mask) | ((transmitter & mask).wrapping_add(index.into()) & mask);
+
+    let b = bssid.to_be_bytes();
+    MacAddr6::new(b[2], b[3], b[4], b[5], b[6], b[7])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::station::tests::station;
+
+    const BSSID: [u8; 6] = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const SHORT_SSID: [u8; 4] = [0x78, 0x56, 0x34, 0x12];
+    const MLD: NeighborMld = NeighborMld {
+        mld_id: 0,
+        link_id: 2,
+    };
+
+    pub(crate) fn tbtt_info(fields: &[&[u8]]) -> Vec<u8> {
+        let mut info = vec![0xff];
+        for field in fields {
+            info.extend_from_slice(field);
+        }
+        info
+    }
+
+    pub(crate) fn rnr_element(operating_class: u8, channel: u8, infos: &[Vec<u8>]) -> Vec<u8> {
+        let len = infos[0].len();
+        let header = (((infos.len() - 1) << 4) | (len << 8)) as u16;
+        let mut body = header.to_le_bytes().to_vec();
+        body.extend_from_slice(&[operating_class, channel]);
+        for info in infos {
+            body.extend_from_slice(info);
+        }
+
+        let mut element = vec![ie::EID_REDUCED_NEIGHBOR_REPORT, body.len() as u8];
+        element.extend(body);
+        element
+    }
+
+    fn colocated(bssid: MacAddr6) -> Vec<u8> {
+        tbtt_info(&[bssid.as_bytes(), &SHORT_SSID, &[BSS_PARAMS_COLOCATED_AP]])
+    }
+
+    #[test]
+    fn parses_every_tbtt_info_layout() {
+        let bssid = Some(MacAddr6::from(BSSID));
+        let short_ssid = Some(0x1234_5678);
+        let psd = &[0x10][..];
+        let mld = &[0x00, 0x32, 0x01][..];
+
+        let layouts: [(&[&[u8]], TbttInfo); 11] = [
+            (&[], (None, None, None, None)),
+            (&[&[0x42]], (None, None, Some(0x42), None)),
+            (&[&SHORT_SSID], (None, short_ssid, None, None)),
+            (
+                &[&SHORT_SSID, &[0x42]],
+                (None, short_ssid, Some(0x42), None),
+            ),
+            (&[&BSSID], (bssid, None, None, None)),
+            (&[&BSSID, &[0x42]], (bssid, None, Some(0x42), None)),
+            (&[&BSSID, &[0x42], psd], (bssid, None, Some(0x42), None)),
+            (&[&BSSID, &SHORT_SSID], (bssid, short_ssid, None, None)),
+            (
+                &[&BSSID, &SHORT_SSID, &[0x42]],
+                (bssid, short_ssid, Some(0x42), None),
+            ),
+            (
+                &[&BSSID, &SHORT_SSID, &[0x42], psd],
+                (bssid, short_ssid, Some(0x42), None),
+            ),
+            (
+                &[&BSSID, &SHORT_SSID, &[0x42], psd, mld],
+                (bssid, short_ssid, Some(0x42), Some(MLD)),
+            ),
+        ];
+
+        for (fields, expected) in layouts {
+            let info = tbtt_info(fields);
+            assert_eq!(
+                parse_tbtt_info(&info),
+                Some(expected),
+                "length {}",
+                info.len()
+            );
+        }
+    }
+
+    #[test]
+    fn longer_tbtt_info_keeps_known_fields() {
+        let info = tbtt_info(&[&BSSID, &SHORT_SSID, &[0x42, 0x10, 0x00, 0x32, 0x01, 0xaa]]);
+
+        assert_eq!(info.len(), 17);
+        assert_eq!(
+            parse_tbtt_info(&info),
+            Some((Some(BSSID.into()), Some(0x1234_5678), Some(0x42), Some(MLD)))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_tbtt_info_lengths() {
+        for len in [0, 3, 4, 10, 14, 15] {
+            assert_eq!(parse_tbtt_info(&vec![0; len]), None, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn parses_reduced_neighbor_report() {
+        let other = [0x02, 0x11, 0x22, 0x33, 0x44, 0x66];
+        let infos = [
+            tbtt_info(&[&BSSID, &SHORT_SSID, &[0x4e]]),
+            tbtt_info(&[&other, &SHORT_SSID, &[0x00]]),
+        ];
+        let element = rnr_element(131, 5, &infos);
+
+        let neighbors = station(MacAddr6::nil(), &element).neighbors();
+
+        assert_eq!(neighbors.len(), 2);
+        assert_eq!(
+            neighbors[0],
+            Neighbor {
+                operating_class: 131,
+                channel: 5,
+                band: Some(Band::Band6GHz),
+                frequency: Some(5975),
+                bssid: Some(BSSID.into()),
+                short_ssid: Some(0x1234_5678),
+                same_ssid: true,
+                multiple_bssid: true,
+                transmitted_bssid: true,
+                colocated_ap: true,
+                mld: None,
+            }
+        );
+        assert_eq!(neighbors[1].bssid, Some(other.into()));
+        assert!(!neighbors[1].colocated_ap);
+    }
+
+    #[test]
+    fn truncated_reduced_neighbor_report_keeps_complete_entries() {
+        let mut body = rnr_element(81, 1, &[colocated(BSSID.into())])[2..].to_vec();
+        body.extend_from_slice(&[0x00, 0x0c, 81, 6, 0xff, 0x02]);
+
+        let neighbors = parse_reduced_neighbor_report(&body);
+
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].frequency, Some(2412));
+    }
+
+    #[test]
+    fn derives_nontransmitted_bssids() {
+        let transmitter = MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0e);
+
+        assert_eq!(
+            nontransmitted_bssid(transmitter, 2, 1),
+            MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0f)
+        );
+        // The index wraps around within the low bits, never carrying into the rest.
+        assert_eq!(
+            nontransmitted_bssid(transmitter, 2, 3),
+            MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0d)
+        );
+        assert_eq!(
+            nontransmitted_bssid(MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x01, 0xff), 8, 1),
+            MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x01, 0x00)
+        );
+    }
+
+    #[test]
+    fn parses_multiple_bssid() {
+        let transmitter = MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0e);
+
+        // Max BSSID indicator of 2, for a set of 4 BSSes.
+        let mut data = vec![0x02];
+        data.extend_from_slice(&[0x00, 0x10]);
+        data.extend_from_slice(&[ie::EID_NONTRANSMITTED_BSSID_CAPABILITY, 0x02, 0x11, 0x04]);
+        data.extend_from_slice(&[ie::EID_SSID, 0x05, b'g', b'u', b'e', b's', b't']);
+        data.extend_from_slice(&[ie::EID_MULTIPLE_BSSID_INDEX, 0x03, 0x03, 0x01, 0x00]);
+        // Continuation of a profile split over two elements, without an index.
+        data.extend_from_slice(&[0x00, 0x03, ie::EID_DS_PARAMETER_SET, 0x01, 0x06]);
+        // Vendor specific subelements are skipped.
+        data.extend_from_slice(&[ie::EID_VENDOR_SPECIFIC, 0x01, 0x00]);
+        data.extend_from_slice(&[0x00, 0x06, ie::EID_SSID, 0x01, b'x']);
+        data.extend_from_slice(&[ie::EID_MULTIPLE_BSSID_INDEX, 0x01, 0x01]);
+
+        let bsses = parse_multiple_bssid(transmitter, &data);
+
+        assert_eq!(
+            bsses,
+            vec![
+                NontransmittedBss {
+                    bssid: MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0d),
+                    ssid: b"guest".to_vec(),
+                    bssid_index: 3,
+                    bss_capability: Some(BssCapability(0x0411)),
+                },
+                NontransmittedBss {
+                    bssid: MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x0f),
+                    ssid: b"x".to_vec(),
+                    bssid_index: 1,
+                    bss_capability: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_access_points() {
+        let bssid = |last| MacAddr6::new(0x02, 0x00, 0x00, 0x00, 0x00, last);
+
+        let transmitted = station(bssid(0x10), &[]);
+        let mut nontransmitted = station(bssid(0x11), &[]);
+        nontransmitted.parent_bssid = Some(bssid(0x10));
+        let six_ghz = station(bssid(0x20), &rnr_element(81, 1, &[colocated(bssid(0x11))]));
+        let unrelated = station(bssid(0x30), &[]);
+        // Only reachable through the 6 GHz BSS, so it takes two merges to join the group.
+        let five_ghz = station(bssid(0x40), &rnr_element(131, 5, &[colocated(bssid(0x20))]));
+        let not_colocated = station(
+            bssid(0x50),
+            &rnr_element(
+                81,
+                1,
+                &[tbtt_info(&[bssid(0x30).as_bytes(), &SHORT_SSID, &[0x00]])],
+            ),
+        );
+
+        let stations = [
+            unrelated,
+            five_ghz,
+            nontransmitted,
+            six_ghz,
+            not_colocated,
+            transmitted,
+        ];
+
+        let groups: Vec<Vec<MacAddr6>> = group_access_points(&stations)
+            .into_iter()
+            .map(|ap| ap.bsses.iter().map(|bss| bss.bssid).collect())
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                vec![bssid(0x30)],
+                vec![bssid(0x40), bssid(0x11), bssid(0x20), bssid(0x10)],
+                vec![bssid(0x50)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,676 @@
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::time::Duration;
+
+use neli::consts::nl::{NlmF, NlmFFlags, Nlmsg};
+use neli::consts::socket::NlFamily;
+use neli::consts::MAX_NL_LENGTH;
+use neli::err::NlError;
+use neli::genl::{Genlmsghdr, Nlattr};
+use neli::nl::{NlPayload, Nlmsghdr};
+use neli::socket::tokio::NlSocket;
+use neli::socket::NlSocketHandle;
+use neli::types::{Buffer, GenlBuffer, NlBuffer};
+use neli::FromBytesWithInput;
+
+use futures::stream::{self, Stream};
+
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::ack::{check_error, enable_extended_ack, parse_ack, sequence_number};
+use crate::enums::{Nl80211Attr, Nl80211Cmd};
+use crate::error::{Error, Result};
+use crate::interface::{Interface, InterfaceId};
+use crate::scan_request::ScanRequest;
+use crate::sched_scan::SchedScanRequest;
+use crate::station::{BssStatus, Station};
+use crate::wiphy::Wiphy;
+
+const NL80211_FAMILY_NAME: &str = "nl80211";
+const SCAN_MULTICAST_NAME: &str = "scan";
+const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanOutcome {
+    Completed,
+    /// The kernel aborted the scan, e.g. because a connection attempt took over the radio.
+    Aborted,
+    /// No completion notification arrived within the scan timeout.
+    TimedOut,
+}
+
+/// Long-lived nl80211 client.
+///
+/// Owns the generic netlink socket and the `scan` multicast subscription, so that the nl80211
+/// family and multicast group are only resolved once.
+pub struct Nl80211 {
+    socket: NlSocket,
+    socket_mcast: NlSocket,
+    nl_id: u16,
+    seq: u32,
+    scan_timeout: Duration,
+}
+
+impl Nl80211 {
+    pub fn new() -> Result<Self> {
+        let (socket, nl_id) = create_main_socket()?;
+        let socket_mcast = create_multicast_socket()?;
+
+        Ok(Nl80211 {
+            socket,
+            socket_mcast,
+            nl_id,
+            seq: 0,
+            scan_timeout: DEFAULT_SCAN_TIMEOUT,
+        })
+    }
+
+    /// Sets how long [`Nl80211::wait_for_scan`] waits for a scan to finish, and a trigger for
+    /// the kernel to announce the scan, 30 seconds by default.
+    pub fn set_scan_timeout(&mut self, scan_timeout: Duration) {
+        self.scan_timeout = scan_timeout;
+    }
+
+    pub async fn interfaces(&mut self) -> Result<Vec<Interface>> {
+        let nl_msghdr = create_get_interface_message(self.nl_id);
+
+        let seq = self.send_request(nl_msghdr).await?;
+
+        recv_all(&mut self.socket, seq, |msg| {
+            Interface::try_from(msg.get_payload().ok()?).ok()
+        })
+        .await
+    }
+
+    pub async fn interface(&mut self, id: impl Into<InterfaceId>) -> Result<Interface> {
+        let id = id.into();
+
+        self.interfaces()
+            .await?
+            .into_iter()
+            .find(|iface| iface.matches(&id))
+            .ok_or(Error::InterfaceNotFound(id))
+    }
+
+    pub async fn wiphy(&mut self, index: u32) -> Result<Wiphy> {
+        let nl_msghdr = create_get_wiphy_message(self.nl_id, index)?;
+
+        let seq = self.send_request(nl_msghdr).await?;
+
+        let payloads = recv_all(&mut self.socket, seq, |msg| match msg.nl_payload {
+            NlPayload::Payload(payload) => Some(payload),
+            _ => None,
+        })
+        .await?;
+
+        let mut wiphy = Wiphy::default();
+        for payload in &payloads {
+            wiphy.merge(payload)?;
+        }
+        Ok(wiphy)
+    }
+
+    /// Triggers a scan and waits for the kernel to announce it, so that
+    /// [`Nl80211::wait_for_scan`] reports the outcome of this scan and not of an earlier one.
+    pub async fn trigger_scan(&mut self, iface: &Interface, request: &ScanRequest) -> Result<()> {
+        // The multicast socket is subscribed for the lifetime of the client, so the completion
+        // notification cannot be missed however quickly the driver reports it.
+        self.drain_notifications().await;
+
+        self.send_trigger_scan(iface, request).await
+    }
+
+    /// Triggers a scan without discarding the queued notifications, which may still report a
+    /// scan the caller is waiting for.
+    async fn send_trigger_scan(&mut self, iface: &Interface, request: &ScanRequest) -> Result<()> {
+        let wiphy = if request.needs_wiphy() {
+            let wiphy = self.wiphy(iface.wiphy).await?;
+            request.validate(&wiphy)?;
+            Some(wiphy)
+        } else {
+            None
+        };
+
+        let nl_msghdr = create_trigger_scan_message(self.nl_id, iface, request, wiphy.as_ref())?;
+
+        self.send_with_ack(nl_msghdr).await?;
+
+        self.wait_for_trigger(iface).await
+    }
+
+    /// Dumps the BSS cache of the interface without triggering a scan, e.g. to reuse the results
+    /// of a scan started by another process. Entries may be stale, see
+    /// [`Station::seen_ms_ago`].
+    pub async fn scan_results(&mut self, iface: &Interface) -> Result<Vec<Station>> {
+        let nl_msghdr = create_get_scan_message(self.nl_id, iface)?;
+
+        let seq = self.send_request(nl_msghdr).await?;
+
+        let payloads = recv_all(&mut self.socket, seq, |msg| match msg.nl_payload {
+            NlPayload::Payload(payload) => Some(payload),
+            _ => None,
+        })
+        .await?;
+
+        // A BSS that cannot be decoded fails the whole dump rather than silently going missing.
+        payloads.iter().map(Station::try_from).collect()
+    }
+
+    /// Returns the BSS the interface is associated with or the IBSS it joined, from the BSS
+    /// cache.
+    pub async fn current_bss(&mut self, iface: &Interface) -> Result<Option<Station>> {
+        Ok(self.scan_results(iface).await?.into_iter().find(|station| {
+            matches!(
+                station.status,
+                Some(BssStatus::Associated | BssStatus::IbssJoined)
+            )
+        }))
+    }
+
+    pub async fn cached_scan_results(
+        &mut self,
+        id: impl Into<InterfaceId>,
+    ) -> Result<Vec<Station>> {
+        let iface = self.interface(id).await?;
+        self.scan_results(&iface).await
+    }
+
+    /// Triggers a passive scan on the interface, waits for it to complete and returns the
+    /// results.
+    pub async fn scan(&mut self, id: impl Into<InterfaceId>) -> Result<Vec<Station>> {
+        self.scan_with(id, &ScanRequest::new()).await
+    }
+
+    pub async fn scan_with(
+        &mut self,
+        id: impl Into<InterfaceId>,
+        request: &ScanRequest,
+    ) -> Result<Vec<Station>> {
+        let iface = self.interface(id).await?;
+
+        self.trigger_scan(&iface, request).await?;
+
+        // Until the kernel reports the scan as finished, dropping this future or bailing out
+        // with an error aborts it. The guard is only armed once the kernel announced our scan,
+        // as a trigger rejected because someone else is scanning must not abort their scan.
+        // Dropping the future before that point leaves an accepted scan running to completion.
+        let mut abort_guard = AbortOnDrop::new(self.nl_id, &iface)?;
+
+        let outcome = self.wait_for_scan(&iface).await?;
+        if outcome != ScanOutcome::TimedOut {
+            abort_guard.disarm();
+        }
+
+        match outcome {
+            ScanOutcome::Completed => self.scan_results(&iface).await,
+            ScanOutcome::Aborted => Err(Error::ScanAborted),
+            ScanOutcome::TimedOut => Err(Error::ScanTimedOut),
+        }
+    }
+
+    /// Aborts the scan running on the interface. Fails with `ENOENT` if there is none.
+    pub async fn abort_scan(&mut self, iface: &Interface) -> Result<()> {
+        let nl_msghdr = create_abort_scan_message(self.nl_id, iface, true)?;
+
+        self.send_with_ack(nl_msghdr).await
+    }
+
+    /// Starts a scheduled scan, which keeps running until stopped with
+    /// [`Nl80211::stop_sched_scan`] and reports matches through
+    /// [`Nl80211::sched_scan_results`].
+    pub async fn start_sched_scan(
+        &mut self,
+        iface: &Interface,
+        request: &SchedScanRequest,
+    ) -> Result<()> {
+        let wiphy = self.wiphy(iface.wiphy).await?;
+        request.validate(&wiphy)?;
+
+        let nl_msghdr = create_start_sched_scan_message(self.nl_id, iface, request)?;
+
+        self.drain_notifications().await;
+
+        self.send_with_ack(nl_msghdr).await
+    }
+
+    pub async fn stop_sched_scan(&mut self, iface: &Interface) -> Result<()> {
+        let nl_msghdr = create_stop_sched_scan_message(self.nl_id, iface)?;
+
+        self.send_with_ack(nl_msghdr).await
+    }
+
+    /// Streams the results each time the scheduled scan running on the interface finds a
+    /// match. The stream ends when the scheduled scan is stopped, by us or by the kernel.
+    pub fn sched_scan_results<'a>(
+        &'a mut self,
+        iface: &Interface,
+    ) -> impl Stream<Item = Result<Vec<Station>>> + 'a {
+        stream::unfold(Some((self, iface.clone())), |state| async move {
+            let (nl80211, iface) = state?;
+
+            let cmd = nl80211
+                .recv_notification(
+                    &iface,
+                    &[Nl80211Cmd::SchedScanResults, Nl80211Cmd::SchedScanStopped],
+                )
+                .await;
+
+            match cmd {
+                Ok(Nl80211Cmd::SchedScanResults) => {
+                    let results = nl80211.scan_results(&iface).await;
+                    Some((results, Some((nl80211, iface))))
+                }
+                Ok(_) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Streams snapshots of the scan results of the interface.
+    ///
+    /// A snapshot is emitted whenever a scan of the interface completes, including scans
+    /// triggered by other processes such as wpa_supplicant or NetworkManager. A scan is only
+    /// triggered when no results arrived for `interval`, and the first one right away.
+    pub fn scan_stream<'a>(
+        &'a mut self,
+        iface: &Interface,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<Station>>> + 'a {
+        stream::unfold(Some((self, iface.clone(), true)), move |state| async move {
+            let (nl80211, iface, first) = state?;
+            let mut trigger = first;
+
+            // Only notifications queued before the stream started are stale. Later ones may
+            // report the completion of a scan the stream triggered itself.
+            if first {
+                nl80211.drain_notifications().await;
+            }
+
+            loop {
+                if trigger {
+                    match nl80211.send_trigger_scan(&iface, &ScanRequest::new()).await {
+                        // Someone else is scanning, their results will do.
+                        Ok(()) | Err(Error::DeviceBusy(_)) => {}
+                        Err(err) => return Some((Err(err), None)),
+                    }
+                }
+
+                // Only the notification is waited for with a timeout, as cancelling the dump
+                // would leave its remaining messages queued on the socket.
+                let recv = nl80211.recv_notification(&iface, &[Nl80211Cmd::NewScanResults]);
+                match timeout(interval, recv).await {
+                    Ok(Ok(_)) => {
+                        let results = nl80211.scan_results(&iface).await;
+                        return Some((results, Some((nl80211, iface, false))));
+                    }
+                    Ok(Err(err)) => return Some((Err(err), None)),
+                    Err(_) => trigger = true,
+                }
+            }
+        })
+    }
+
+    /// Streams the results of every scan of the interface, without ever triggering one, so
+    /// that monitoring does not compete with the connection manager for the radio.
+    pub fn watch_scan_results<'a>(
+        &'a mut self,
+        iface: &Interface,
+    ) -> impl Stream<Item = Result<Vec<Station>>> + 'a {
+        stream::unfold(Some((self, iface.clone())), |state| async move {
+            let (nl80211, iface) = state?;
+
+            match nl80211.next_scan_results(&iface).await {
+                Ok(results) => Some((Ok(results), Some((nl80211, iface)))),
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Sends the results of every scan of the interface to the channel, without triggering
+    /// any. Returns as soon as the receiver is dropped, even while waiting for a scan.
+    pub async fn forward_scan_results(
+        &mut self,
+        iface: &Interface,
+        sender: mpsc::Sender<Vec<Station>>,
+    ) -> Result<()> {
+        loop {
+            // Replies of a dump cut short are skipped by sequence number, so giving up halfway
+            // leaves the client usable.
+            let results = tokio::select! {
+                results = self.next_scan_results(iface) => results?,
+                () = sender.closed() => return Ok(()),
+            };
+            if sender.send(results).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Waits for the next scan of the interface, by whoever triggered it, and dumps its
+    /// results.
+    async fn next_scan_results(&mut self, iface: &Interface) -> Result<Vec<Station>> {
+        self.recv_notification(iface, &[Nl80211Cmd::NewScanResults])
+            .await?;
+        self.scan_results(iface).await
+    }
+
+    /// Sends a request with a sequence number of its own, so that its replies can be told apart
+    /// from leftovers of earlier requests, such as a dump whose future was dropped halfway.
+    async fn send_request(
+        &mut self,
+        mut nl_msghdr: Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>,
+    ) -> Result<u32> {
+        self.seq = self.seq.wrapping_add(1);
+        nl_msghdr.nl_seq = self.seq;
+
+        self.socket.send(&nl_msghdr).await?;
+
+        Ok(self.seq)
+    }
+
+    async fn send_with_ack(
+        &mut self,
+        nl_msghdr: Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>,
+    ) -> Result<()> {
+        let seq = self.send_request(nl_msghdr).await?;
+
+        let mut buf = vec![0; MAX_NL_LENGTH];
+
+        loop {
+            let len = self.socket.read(&mut buf).await?;
+            let buf = &buf[..len];
+
+            if sequence_number(buf) == Some(seq) {
+                return parse_ack(buf);
+            }
+        }
+    }
+
+    /// Discards notifications queued on the multicast socket, which would otherwise have to be
+    /// skipped one by one while waiting for the trigger notification of the next scan.
+    async fn drain_notifications(&mut self) {
+        loop {
+            let mut buf = vec![0; MAX_NL_LENGTH];
+            let recv = self
+                .socket_mcast
+                .recv::<Nlmsg, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>(&mut buf);
+            if timeout(Duration::ZERO, recv).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Waits for the kernel to announce the scan it accepted from us. Completion notifications
+    /// received before are skipped, as they belong to an earlier scan, e.g. one of another
+    /// process finishing right before ours was triggered.
+    async fn wait_for_trigger(&mut self, iface: &Interface) -> Result<()> {
+        let scan_timeout = self.scan_timeout;
+        let recv = self.recv_notification(iface, &[Nl80211Cmd::TriggerScan]);
+        match timeout(scan_timeout, recv).await {
+            Ok(cmd) => cmd.map(drop),
+            Err(_) => Err(Error::ScanTimedOut),
+        }
+    }
+
+    /// Waits for the scan triggered on the interface to finish, skipping notifications about
+    /// other interfaces.
+    pub async fn wait_for_scan(&mut self, iface: &Interface) -> Result<ScanOutcome> {
+        match timeout(self.scan_timeout, self.recv_scan_outcome(iface)).await {
+            Ok(outcome) => outcome,
+            Err(_) => Ok(ScanOutcome::TimedOut),
+        }
+    }
+
+    async fn recv_scan_outcome(&mut self, iface: &Interface) -> Result<ScanOutcome> {
+        let cmd = self
+            .recv_notification(
+                iface,
+                &[Nl80211Cmd::NewScanResults, Nl80211Cmd::ScanAborted],
+            )
+            .await?;
+
+        match cmd {
+            Nl80211Cmd::NewScanResults => Ok(ScanOutcome::Completed),
+            _ => Ok(ScanOutcome::Aborted),
+        }
+    }
+
+    /// Waits for one of the given notifications about the interface.
+    async fn recv_notification(
+        &mut self,
+        iface: &Interface,
+        cmds: &[Nl80211Cmd],
+    ) -> Result<Nl80211Cmd> {
+        loop {
+            let mut buf = vec![0; MAX_NL_LENGTH];
+            let msgs = self
+                .socket_mcast
+                .recv::<Nlmsg, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>(&mut buf)
+                .await?;
+
+            for payload in msgs
+                .iter()
+                .filter_map(|nl_msghdr| nl_msghdr.get_payload().ok())
+                .filter(|payload| is_for_interface(payload, iface))
+            {
+                if cmds.contains(&payload.cmd) {
+                    return Ok(payload.cmd);
+                }
+            }
+        }
+    }
+}
+
+/// Aborts the scan on an interface when dropped, unless disarmed.
+///
+/// The client socket is borrowed by the scan future being dropped, so the abort request is sent
+/// from a throwaway blocking socket without waiting for the acknowledgement.
+struct AbortOnDrop {
+    nl_msghdr: Option<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>>,
+}
+
+impl AbortOnDrop {
+    fn new(nl_id: u16, iface: &Interface) -> Result<Self> {
+        Ok(AbortOnDrop {
+            nl_msghdr: Some(create_abort_scan_message(nl_id, iface, false)?),
+        })
+    }
+
+    fn disarm(&mut self) {
+        self.nl_msghdr = None;
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(nl_msghdr) = self.nl_msghdr.take() {
+            if let Ok(mut socket_handle) = NlSocketHandle::connect(NlFamily::Generic, None, &[]) {
+                let _ = socket_handle.send(nl_msghdr);
+            }
+        }
+    }
+}
+
+/// Checks whether a notification refers to the interface, by ifindex if it has a netdev and by
+/// wdev otherwise.
+fn is_for_interface(payload: &Genlmsghdr<Nl80211Cmd, Nl80211Attr>, iface: &Interface) -> bool {
+    let attrs = payload.get_attr_handle();
+    match iface.index {
+        Some(index) => attrs.get_attr_payload_as::<u32>(Nl80211Attr::Ifindex).ok() == Some(index),
+        None => attrs.get_attr_payload_as::<u64>(Nl80211Attr::Wdev).ok() == Some(iface.wdev),
+    }
+}
+
+fn create_main_socket() -> Result<(NlSocket, u16)> {
+    let mut socket_handle = NlSocketHandle::connect(NlFamily::Generic, None, &[])?;
+
+    let nl_id = socket_handle
+        .resolve_genl_family(NL80211_FAMILY_NAME)
+        .map_err(|err| match err {
+            NlError::Msg(_) => Error::FamilyNotFound,
+            NlError::Nlmsgerr(err) if -err.error == libc::ENOENT => Error::FamilyNotFound,
+            err => err.into(),
+        })?;
+
+    enable_extended_ack(&socket_handle)?;
+
+    let socket = NlSocket::new(socket_handle)?;
+
+    Ok((socket, nl_id))
+}
+
+fn create_multicast_socket() -> Result<NlSocket> {
+    let mut socket_handle_mcast = NlSocketHandle::connect(NlFamily::Generic, None, &[])?;
+
+    let mcast_id =
+        socket_handle_mcast.resolve_nl_mcast_group(NL80211_FAMILY_NAME, SCAN_MULTICAST_NAME)?;
+    socket_handle_mcast.add_mcast_membership(&[mcast_id])?;
+
+    Ok(NlSocket::new(socket_handle_mcast)?)
+}
+
+fn create_get_interface_message(nl_id: u16) -> Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>> {
+    let attrs = GenlBuffer::<Nl80211Attr, Buffer>::new();
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::GetInterface, 1, attrs);
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Dump]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Nlmsghdr::new(None, nl_id, flags, None, None, payload)
+}
+
+fn create_interface_attr(iface: &Interface) -> Result<Nlattr<Nl80211Attr, Buffer>> {
+    let attr = match iface.index {
+        Some(index) => Nlattr::new(false, true, Nl80211Attr::Ifindex, index)?,
+        None => Nlattr::new(false, true, Nl80211Attr::Wdev, iface.wdev)?,
+    };
+    Ok(attr)
+}
+
+fn create_get_wiphy_message(
+    nl_id: u16,
+    index: u32,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let wiphy_attr = Nlattr::new(false, true, Nl80211Attr::Wiphy, index)?;
+    let split_attr = Nlattr::new(false, true, Nl80211Attr::SplitWiphyDump, ())?;
+    let genl_msghdr = Genlmsghdr::new(
+        Nl80211Cmd::GetWiphy,
+        1,
+        [wiphy_attr, split_attr].into_iter().collect(),
+    );
+
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Dump]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+fn create_trigger_scan_message(
+    nl_id: u16,
+    iface: &Interface,
+    request: &ScanRequest,
+    wiphy: Option<&Wiphy>,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let iface_attr = create_interface_attr(iface)?;
+    let attrs = [iface_attr]
+        .into_iter()
+        .chain(request.attrs(wiphy)?)
+        .collect();
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::TriggerScan, 1, attrs);
+
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Ack]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+fn create_abort_scan_message(
+    nl_id: u16,
+    iface: &Interface,
+    ack: bool,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let attr = create_interface_attr(iface)?;
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::AbortScan, 1, [attr].into_iter().collect());
+
+    let flags = if ack {
+        NlmFFlags::new(&[NlmF::Request, NlmF::Ack])
+    } else {
+        NlmFFlags::new(&[NlmF::Request])
+    };
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+fn create_start_sched_scan_message(
+    nl_id: u16,
+    iface: &Interface,
+    request: &SchedScanRequest,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let iface_attr = create_interface_attr(iface)?;
+    let attrs = [iface_attr].into_iter().chain(request.attrs()?).collect();
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::StartSchedScan, 1, attrs);
+
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Ack]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+fn create_stop_sched_scan_message(
+    nl_id: u16,
+    iface: &Interface,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let attr = create_interface_attr(iface)?;
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::StopSchedScan, 1, [attr].into_iter().collect());
+
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Ack]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+fn create_get_scan_message(
+    nl_id: u16,
+    iface: &Interface,
+) -> Result<Nlmsghdr<u16, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>> {
+    let attr = create_interface_attr(iface)?;
+    let genl_msghdr = Genlmsghdr::new(Nl80211Cmd::GetScan, 1, [attr].into_iter().collect());
+
+    let flags = NlmFFlags::new(&[NlmF::Request, NlmF::Dump]);
+    let payload = NlPayload::Payload(genl_msghdr);
+    Ok(Nlmsghdr::new(None, nl_id, flags, None, None, payload))
+}
+
+async fn recv_all<T, F>(socket: &mut NlSocket, seq: u32, mut f: F) -> Result<Vec<T>>
+where
+    F: FnMut(Nlmsghdr<Nlmsg, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>) -> Option<T>,
+{
+    let mut items = Vec::new();
+
+    'outer: loop {
+        let mut buf = vec![0; MAX_NL_LENGTH];
+
+        let len = socket.read(&mut buf).await?;
+        let buf = &buf[..len];
+
+        if sequence_number(buf) != Some(seq) {
+            continue;
+        }
+
+        // Errors are decoded by hand, as neli cannot parse the extended ack attributes.
+        check_error(buf)?;
+
+        let msgs = NlBuffer::<Nlmsg, Genlmsghdr<Nl80211Cmd, Nl80211Attr>>::from_bytes_with_input(
+            &mut Cursor::new(buf),
+            len,
+        )?;
+
+        for msg in msgs {
+            if msg.nl_type == Nlmsg::Done {
+                break 'outer;
+            }
+
+            if let Some(item) = f(msg) {
+                items.push(item);
+            }
+        }
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,535 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use macaddr::MacAddr6;
+
+use neli::genl::Nlattr;
+use neli::types::Buffer;
+use neli::{Size, ToBytes};
+
+use crate::consts;
+use crate::enums::Nl80211Attr;
+use crate::error::{Error, Result};
+use crate::frequency::Band;
+use crate::wiphy::Wiphy;
+
+const SSID_MAX_LEN: usize = 32;
+
+/// Set of `NL80211_SCAN_FLAG_*` flags modifying how a scan is performed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ScanFlags(u32);
+
+impl ScanFlags {
+    /// Scans with lower priority, so the driver may interrupt it for other traffic. Needs
+    /// `NL80211_FEATURE_LOW_PRIORITY_SCAN`.
+    pub const LOW_PRIORITY: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_LOW_PRIORITY);
+    /// Flushes cached results older than the scan when it completes. Needs
+    /// `NL80211_FEATURE_SCAN_FLUSH`.
+    pub const FLUSH: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_FLUSH);
+    /// Sends probe requests from a random MAC address, see [`ScanRequest::random_mac`]. Needs
+    /// `NL80211_FEATURE_SCAN_RANDOM_MAC_ADDR`.
+    pub const RANDOM_ADDR: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_RANDOM_ADDR);
+    /// Spends as little time as possible off the operating channel, at the cost of missing
+    /// some BSSes. Needs `NL80211_EXT_FEATURE_LOW_SPAN_SCAN`.
+    pub const LOW_SPAN: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_LOW_SPAN);
+    /// Keeps power consumption low, e.g. by scanning with fewer antennas, at the cost of range.
+    /// Needs `NL80211_EXT_FEATURE_LOW_POWER_SCAN`.
+    pub const LOW_POWER: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_LOW_POWER);
+    /// Finds as many BSSes as possible, even if the scan takes longer or uses more power.
+    /// Needs `NL80211_EXT_FEATURE_HIGH_ACCURACY_SCAN`.
+    pub const HIGH_ACCURACY: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_HIGH_ACCURACY);
+    /// Also scans 6 GHz channels reported by co-located 2.4/5 GHz access points.
+    pub const COLOCATED_6GHZ: ScanFlags = ScanFlags(consts::NL80211_SCAN_FLAG_COLOCATED_6GHZ);
+
+    pub const fn empty() -> Self {
+        ScanFlags(0)
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(&self, other: ScanFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ScanFlags {
+    type Output = ScanFlags;
+
+    fn bitor(self, rhs: ScanFlags) -> ScanFlags {
+        ScanFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ScanFlags {
+    fn bitor_assign(&mut self, rhs: ScanFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Parameters of a triggered scan.
+///
+/// Without any SSIDs the scan is passive: the device only listens for beacons. Listing SSIDs
+/// makes it send directed probe requests, which is the only way to find hidden networks.
+///
+/// Without any frequencies or bands all channels of the wiphy are scanned. Restricting the scan
+/// to a few channels, e.g. 2412, 2437 and 2462 MHz for channels 1, 6 and 11, makes it finish
+/// much sooner.
+///
+/// Flags are checked against the features of the wiphy before the scan is triggered.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ScanRequest {
+    ssids: Vec<Vec<u8>>,
+    frequencies: Vec<u32>,
+    bands: Vec<Band>,
+    flags: ScanFlags,
+    passive: bool,
+    /// Address and mask of the bits to keep from it when randomizing the MAC address.
+    mac_template: Option<(MacAddr6, MacAddr6)>,
+}
+
+impl ScanRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Actively probes for the given SSID.
+    pub fn ssid(mut self, ssid: impl AsRef<[u8]>) -> Self {
+        self.ssids.push(ssid.as_ref().to_vec());
+        self
+    }
+
+    /// Sends wildcard probe requests, which any non-hidden network answers.
+    pub fn wildcard(self) -> Self {
+        self.ssid([])
+    }
+
+    /// Scans the channel with the given centre frequency in MHz.
+    pub fn frequency(mut self, frequency: u32) -> Self {
+        self.frequencies.push(frequency);
+        self
+    }
+
+    /// Scans every enabled channel of the band.
+    pub fn band(mut self, band: Band) -> Self {
+        self.bands.push(band);
+        self
+    }
+
+    pub fn flags(mut self, flags: ScanFlags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Only listens for beacons and never sends probe requests, which conflicts with probing
+    /// for SSIDs.
+    pub fn passive(mut self) -> Self {
+        self.passive = true;
+        self
+    }
+
+    /// Sends probe requests from a random MAC address, keeping the bits of `mac` that are set
+    /// in `mask`, e.g. to preserve a vendor OUI.
+    pub fn random_mac(mut self, mac: MacAddr6, mask: MacAddr6) -> Self {
+        self.flags |= ScanFlags::RANDOM_ADDR;
+        self.mac_template = Some((mac, mask));
+        self
+    }
+
+    pub fn ssids(&self) -> &[Vec<u8>] {
+        &self.ssids
+    }
+
+    pub fn frequencies(&self) -> &[u32] {
+        &self.frequencies
+    }
+
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    pub fn scan_flags(&self) -> ScanFlags {
+        self.flags
+    }
+
+    pub fn is_passive(&self) -> bool {
+        self.passive
+    }
+
+    pub(crate) fn needs_wiphy(&self) -> bool {
+        !self.ssids.is_empty()
+            || !self.frequencies.is_empty()
+            || !self.bands.is_empty()
+            || !self.flags.is_empty()
+    }
+
+    pub(crate) fn validate(&self, wiphy: &Wiphy) -> Result<()> {
+        if self.passive && !self.ssids.is_empty() {
+            return Err(Error::InvalidScanRequest(
+                "Passive scans cannot probe for SSIDs".to_owned(),
+            ));
+        }
+
+        self.validate_flags(wiphy)?;
+
+        validate_ssids(&self.ssids, wiphy.max_scan_ssids, wiphy)?;
+        validate_frequencies(&self.frequencies, wiphy)?;
+
+        for &band in &self.bands {
+            let enabled = wiphy
+                .band(band)
+                .map(|b| b.frequencies.iter().any(|freq| !freq.disabled))
+                .unwrap_or_default();
+            // An empty frequency list would silently turn into a scan of all channels.
+            if !enabled {
+                return Err(Error::InvalidScanRequest(format!(
+                    "{:?} has no enabled channels on {}",
+                    band, wiphy.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_flags(&self, wiphy: &Wiphy) -> Result<()> {
+        let features = [
+            (
+                ScanFlags::LOW_PRIORITY,
+                "low priority",
+                wiphy.has_feature(consts::NL80211_FEATURE_LOW_PRIORITY_SCAN),
+            ),
+            (
+                ScanFlags::FLUSH,
+                "flush",
+                wiphy.has_feature(consts::NL80211_FEATURE_SCAN_FLUSH),
+            ),
+            (
+                ScanFlags::RANDOM_ADDR,
+                "random MAC address",
+                wiphy.has_feature(consts::NL80211_FEATURE_SCAN_RANDOM_MAC_ADDR),
+            ),
+            (
+                ScanFlags::LOW_SPAN,
+                "low span",
+                wiphy.has_ext_feature(consts::NL80211_EXT_FEATURE_LOW_SPAN_SCAN),
+            ),
+            (
+                ScanFlags::LOW_POWER,
+                "low power",
+                wiphy.has_ext_feature(consts::NL80211_EXT_FEATURE_LOW_POWER_SCAN),
+            ),
+            (
+                ScanFlags::HIGH_ACCURACY,
+                "high accuracy",
+                wiphy.has_ext_feature(consts::NL80211_EXT_FEATURE_HIGH_ACCURACY_SCAN),
+            ),
+        ];
+
+        for (flag, name, supported) in features {
+            if self.flags.contains(flag) && !supported {
+                return Err(Error::InvalidScanRequest(format!(
+                    "{} scans are not supported by {}",
+                    name, wiphy.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Expands the requested bands into their enabled channels and adds the explicitly
+    /// requested frequencies, without duplicates.
+    fn resolve_frequencies(&self, wiphy: Option<&Wiphy>) -> Vec<u32> {
+        let band_frequencies = wiphy
+            .into_iter()
+            .flat_map(|wiphy| &wiphy.bands)
+            .filter(|band| self.bands.contains(&band.band))
+            .flat_map(|band| &band.frequencies)
+            .filter(|freq| !freq.disabled)
+            .map(|freq| freq.frequency);
+
+        let mut frequencies = Vec::new();
+        for frequency in self.frequencies.iter().copied().chain(band_frequencies) {
+            if !frequencies.contains(&frequency) {
+                frequencies.push(frequency);
+            }
+        }
+        frequencies
+    }
+
+    pub(crate) fn attrs(&self, wiphy: Option<&Wiphy>) -> Result<Vec<Nlattr<Nl80211Attr, Buffer>>> {
+        // The AP flag allows scanning on AP interfaces and is ignored for other types.
+        let flags = self.flags.bits() | consts::NL80211_SCAN_FLAG_AP;
+        let mut attrs = vec![Nlattr::new(false, true, Nl80211Attr::ScanFlags, flags)?];
+
+        if let Some((mac, mask)) = self.mac_template {
+            attrs.push(Nlattr::new(false, false, Nl80211Attr::Mac, mac.as_bytes())?);
+            attrs.push(Nlattr::new(
+                false,
+                false,
+                Nl80211Attr::MacMask,
+                mask.as_bytes(),
+            )?);
+        }
+
+        if !self.ssids.is_empty() {
+            attrs.push(list_attr(
+                Nl80211Attr::ScanSsids,
+                self.ssids.iter().map(Vec::as_slice),
+            )?);
+        }
+
+        let frequencies = self.resolve_frequencies(wiphy);
+        if !frequencies.is_empty() {
+            attrs.push(list_attr(Nl80211Attr::ScanFrequencies, frequencies)?);
+        }
+
+        Ok(attrs)
+    }
+}
+
+pub(crate) fn validate_ssids(ssids: &[Vec<u8>], max_ssids: u8, wiphy: &Wiphy) -> Result<()> {
+    if ssids.len() > max_ssids as usize {
+        return Err(Error::InvalidScanRequest(format!(
+            "{} SSIDs requested, {} supports at most {}",
+            ssids.len(),
+            wiphy.name,
+            max_ssids
+        )));
+    }
+
+    validate_ssid_lengths(ssids.iter().map(Vec::as_slice))
+}
+
+pub(crate) fn validate_ssid_lengths<'a>(mut ssids: impl Iterator<Item = &'a [u8]>) -> Result<()> {
+    if let Some(ssid) = ssids.find(|ssid| ssid.len() > SSID_MAX_LEN) {
+        return Err(Error::InvalidScanRequest(format!(
+            "SSID of {} bytes exceeds the maximum of {}",
+            ssid.len(),
+            SSID_MAX_LEN
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_frequencies(frequencies: &[u32], wiphy: &Wiphy) -> Result<()> {
+    for &frequency in frequencies {
+        match wiphy.frequency(frequency) {
+            Some(freq) if !freq.disabled => {}
+            Some(_) => {
+                return Err(Error::InvalidScanRequest(format!(
+                    "{} MHz is disabled on {}",
+                    frequency, wiphy.name
+                )))
+            }
+            None => {
+                return Err(Error::InvalidScanRequest(format!(
+                    "{} MHz is not supported by {}",
+                    frequency, wiphy.name
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a nested attribute holding a list, with each item as an attribute indexed from 1.
+pub(crate) fn list_attr<P>(
+    attr: Nl80211Attr,
+    items: impl IntoIterator<Item = P>,
+) -> Result<Nlattr<Nl80211Attr, Buffer>>
+where
+    P: Size + ToBytes,
+{
+    let mut list_attr = Nlattr::new(true, false, attr, Buffer::new())?;
+    for (i, item) in items.into_iter().enumerate() {
+        list_attr.add_nested_attribute(&Nlattr::new(false, false, i as u16 + 1, item)?)?;
+    }
+    Ok(list_attr)
+}
+
+#[cfg(test)]
+mod tests {
+    use neli::attr::Attribute;
+
+    use super::*;
+    use crate::wiphy::{WiphyBand, WiphyFrequency};
+
+    fn channel(frequency: u32, disabled: bool) -> WiphyFrequency {
+        WiphyFrequency {
+            frequency,
+            disabled,
+            no_ir: false,
+            radar: false,
+        }
+    }
+
+    fn wiphy() -> Wiphy {
+        Wiphy {
+            name: "phy0".to_owned(),
+            max_scan_ssids: 4,
+            bands: vec![
+                WiphyBand {
+                    band: Band::Band2GHz,
+                    frequencies: vec![
+                        channel(2412, false),
+                        channel(2437, false),
+                        channel(2484, true),
+                    ],
+                },
+                WiphyBand {
+                    band: Band::Band5GHz,
+                    frequencies: vec![channel(5180, false), channel(5200, true)],
+                },
+                WiphyBand {
+                    band: Band::Band6GHz,
+                    frequencies: vec![channel(5955, true)],
+                },
+            ],
+            ..Wiphy::default()
+        }
+    }
+
+    fn invalid(result: Result<()>) -> String {
+        match result {
+            Err(Error::InvalidScanRequest(msg)) => msg,
+            result => panic!("Expected an invalid scan request, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn passive_scan_with_ssid_is_rejected() {
+        let msg = invalid(ScanRequest::new().passive().ssid("home").validate(&wiphy()));
+
+        assert_eq!(msg, "Passive scans cannot probe for SSIDs");
+    }
+
+    #[test]
+    fn unsupported_flags_are_rejected() {
+        let flags = [
+            (ScanFlags::LOW_PRIORITY, "low priority"),
+            (ScanFlags::FLUSH, "flush"),
+            (ScanFlags::RANDOM_ADDR, "random MAC address"),
+            (ScanFlags::LOW_SPAN, "low span"),
+            (ScanFlags::LOW_POWER, "low power"),
+            (ScanFlags::HIGH_ACCURACY, "high accuracy"),
+        ];
+
+        for (flag, name) in flags {
+            let msg = invalid(ScanRequest::new().flags(flag).validate(&wiphy()));
+
+            assert_eq!(msg, format!("{} scans are not supported by phy0", name));
+        }
+    }
+
+    #[test]
+    fn supported_flags_are_accepted() {
+        let mut ext_features = vec![0; 8];
+        for index in [
+            consts::NL80211_EXT_FEATURE_LOW_SPAN_SCAN,
+            consts::NL80211_EXT_FEATURE_LOW_POWER_SCAN,
+            consts::NL80211_EXT_FEATURE_HIGH_ACCURACY_SCAN,
+        ] {
+            ext_features[index as usize / 8] |= 1 << (index % 8);
+        }
+        let wiphy = Wiphy {
+            feature_flags: consts::NL80211_FEATURE_LOW_PRIORITY_SCAN
+                | consts::NL80211_FEATURE_SCAN_FLUSH
+                | consts::NL80211_FEATURE_SCAN_RANDOM_MAC_ADDR,
+            ext_features,
+            ..wiphy()
+        };
+        let request = ScanRequest::new().flags(
+            ScanFlags::LOW_PRIORITY
+                | ScanFlags::FLUSH
+                | ScanFlags::RANDOM_ADDR
+                | ScanFlags::LOW_SPAN
+                | ScanFlags::LOW_POWER
+                | ScanFlags::HIGH_ACCURACY,
+        );
+
+        request.validate(&wiphy).unwrap();
+    }
+
+    #[test]
+    fn band_expansion_skips_disabled_channels() {
+        let wiphy = wiphy();
+        let request = ScanRequest::new().band(Band::Band2GHz).band(Band::Band5GHz);
+
+        request.validate(&wiphy).unwrap();
+
+        assert_eq!(
+            request.resolve_frequencies(Some(&wiphy)),
+            vec![2412, 2437, 5180]
+        );
+    }
+
+    #[test]
+    fn frequencies_are_deduplicated() {
+        let wiphy = wiphy();
+        let request = ScanRequest::new()
+            .frequency(5180)
+            .frequency(2437)
+            .frequency(5180)
+            .band(Band::Band2GHz);
+
+        request.validate(&wiphy).unwrap();
+
+        assert_eq!(
+            request.resolve_frequencies(Some(&wiphy)),
+            vec![5180, 2437, 2412]
+        );
+    }
+
+    #[test]
+    fn attrs_list_resolved_frequencies() {
+        let wiphy = wiphy();
+        let request = ScanRequest::new().frequency(5180).band(Band::Band2GHz);
+
+        let attrs = request.attrs(Some(&wiphy)).unwrap();
+
+        let frequencies = attrs
+            .iter()
+            .find(|attr| attr.nla_type.nla_type == Nl80211Attr::ScanFrequencies)
+            .unwrap()
+            .get_attr_handle::<u16>()
+            .unwrap();
+        let frequencies: Vec<u32> = frequencies
+            .iter()
+            .map(|attr| attr.get_payload_as().unwrap())
+            .collect();
+        assert_eq!(frequencies, vec![5180, 2412, 2437]);
+    }
+
+    #[test]
+    fn band_without_enabled_channels_is_rejected() {
+        let wiphy = wiphy();
+
+        let msg = invalid(ScanRequest::new().band(Band::Band6GHz).validate(&wiphy));
+        assert_eq!(msg, "Band6GHz has no enabled channels on phy0");
+
+        let msg = invalid(ScanRequest::new().band(Band::Band60GHz).validate(&wiphy));
+        assert_eq!(msg, "Band60GHz has no enabled channels on phy0");
+    }
+
+    #[test]
+    fn disabled_frequency_is_rejected() {
+        let msg = invalid(ScanRequest::new().frequency(5200).validate(&wiphy()));
+
+        assert_eq!(msg, "5200 MHz is disabled on phy0");
+    }
+
+    #[test]
+    fn unknown_frequency_is_rejected() {
+        let msg = invalid(ScanRequest::new().frequency(5170).validate(&wiphy()));
+
+        assert_eq!(msg, "5170 MHz is not supported by phy0");
+    }
+}
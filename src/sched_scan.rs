@@ -0,0 +1,317 @@
+use std::time::Duration;
+
+use neli::genl::Nlattr;
+use neli::types::Buffer;
+
+use crate::consts;
+use crate::enums::{Nl80211Attr, Nl80211SchedScanMatchAttr, Nl80211SchedScanPlan};
+use crate::error::{Error, Result};
+use crate::scan_request::{list_attr, validate_frequencies, validate_ssid_lengths, validate_ssids};
+use crate::wiphy::Wiphy;
+
+/// Filter deciding which networks found by a scheduled scan are reported.
+///
+/// A match set without an SSID only sets the default RSSI threshold of the other match sets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MatchSet {
+    pub ssid: Option<Vec<u8>>,
+    /// Networks with a weaker signal than this are not reported.
+    pub rssi_threshold_dbm: Option<i32>,
+}
+
+impl MatchSet {
+    pub fn ssid(ssid: impl AsRef<[u8]>) -> Self {
+        MatchSet {
+            ssid: Some(ssid.as_ref().to_vec()),
+            rssi_threshold_dbm: None,
+        }
+    }
+
+    pub fn rssi_threshold(mut self, dbm: i32) -> Self {
+        self.rssi_threshold_dbm = Some(dbm);
+        self
+    }
+}
+
+/// Scans every `interval`, `iterations` times or forever for the last plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScanPlan {
+    pub interval: Duration,
+    pub iterations: Option<u32>,
+}
+
+/// Parameters of a scheduled scan, which the firmware runs periodically on its own, e.g. while
+/// the host is suspended, and which only reports networks matching one of the match sets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SchedScanRequest {
+    ssids: Vec<Vec<u8>>,
+    frequencies: Vec<u32>,
+    match_sets: Vec<MatchSet>,
+    plans: Vec<ScanPlan>,
+    relative_rssi: Option<i8>,
+}
+
+impl SchedScanRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Actively probes for the given SSID.
+    pub fn ssid(mut self, ssid: impl AsRef<[u8]>) -> Self {
+        self.ssids.push(ssid.as_ref().to_vec());
+        self
+    }
+
+    /// Scans the channel with the given centre frequency in MHz.
+    pub fn frequency(mut self, frequency: u32) -> Self {
+        self.frequencies.push(frequency);
+        self
+    }
+
+    pub fn match_set(mut self, match_set: MatchSet) -> Self {
+        self.match_sets.push(match_set);
+        self
+    }
+
+    /// Adds a plan running a limited number of scans, before the following plans.
+    pub fn plan(mut self, interval: Duration, iterations: u32) -> Self {
+        self.plans.push(ScanPlan {
+            interval,
+            iterations: Some(iterations),
+        });
+        self
+    }
+
+    /// Adds the final plan, scanning every `interval` until the scheduled scan is stopped.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.plans.push(ScanPlan {
+            interval,
+            iterations: None,
+        });
+        self
+    }
+
+    /// Only reports networks whose signal is at least `db` stronger than the one of the
+    /// current connection.
+    pub fn relative_rssi(mut self, db: i8) -> Self {
+        self.relative_rssi = Some(db);
+        self
+    }
+
+    pub fn ssids(&self) -> &[Vec<u8>] {
+        &self.ssids
+    }
+
+    pub fn frequencies(&self) -> &[u32] {
+        &self.frequencies
+    }
+
+    pub fn match_sets(&self) -> &[MatchSet] {
+        &self.match_sets
+    }
+
+    pub fn plans(&self) -> &[ScanPlan] {
+        &self.plans
+    }
+
+    pub(crate) fn validate(&self, wiphy: &Wiphy) -> Result<()> {
+        validate_ssids(&self.ssids, wiphy.max_sched_scan_ssids, wiphy)?;
+        validate_frequencies(&self.frequencies, wiphy)?;
+        self.validate_plans(wiphy)?;
+
+        if self.match_sets.len() > wiphy.max_match_sets as usize {
+            return Err(Error::InvalidScanRequest(format!(
+                "{} match sets requested, {} supports at most {}",
+                self.match_sets.len(),
+                wiphy.name,
+                wiphy.max_match_sets
+            )));
+        }
+        validate_ssid_lengths(self.match_sets.iter().filter_map(|m| m.ssid.as_deref()))?;
+
+        if self.relative_rssi.is_some()
+            && !wiphy.has_ext_feature(consts::NL80211_EXT_FEATURE_SCHED_SCAN_RELATIVE_RSSI)
+        {
+            return Err(Error::InvalidScanRequest(format!(
+                "relative RSSI is not supported by {}",
+                wiphy.name
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn validate_plans(&self, wiphy: &Wiphy) -> Result<()> {
+        let (last, limited) = match self.plans.split_last() {
+            Some(plans) => plans,
+            None => {
+                return Err(Error::InvalidScanRequest(
+                    "Scheduled scans need an interval".to_owned(),
+                ))
+            }
+        };
+
+        if last.iterations.is_some() || limited.iter().any(|plan| plan.iterations.is_none()) {
+            return Err(Error::InvalidScanRequest(
+                "Only the last scan plan can run forever, and it must".to_owned(),
+            ));
+        }
+
+        if self.plans.len() > wiphy.max_sched_scan_plans as usize {
+            return Err(Error::InvalidScanRequest(format!(
+                "{} scan plans requested, {} supports at most {}",
+                self.plans.len(),
+                wiphy.name,
+                wiphy.max_sched_scan_plans
+            )));
+        }
+
+        for plan in &self.plans {
+            let interval = plan.interval.as_secs();
+            if interval == 0 || interval > u64::from(wiphy.max_sched_scan_plan_interval) {
+                return Err(Error::InvalidScanRequest(format!(
+                    "Scan plan interval of {}s is not within 1s and {}s",
+                    interval, wiphy.max_sched_scan_plan_interval
+                )));
+            }
+
+            // The last plan runs forever, which even drivers limited to a single plan and
+            // thus reporting 0 iterations support.
+            if let Some(iterations) = plan.iterations {
+                if iterations == 0 || iterations > wiphy.max_sched_scan_plan_iterations {
+                    return Err(Error::InvalidScanRequest(format!(
+                        "{} scan plan iterations are not within 1 and {}",
+                        iterations, wiphy.max_sched_scan_plan_iterations
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn attrs(&self) -> Result<Vec<Nlattr<Nl80211Attr, Buffer>>> {
+        let mut attrs = Vec::new();
+
+        if !self.ssids.is_empty() {
+            attrs.push(list_attr(
+                Nl80211Attr::ScanSsids,
+                self.ssids.iter().map(Vec::as_slice),
+            )?);
+        }
+
+        if !self.frequencies.is_empty() {
+            attrs.push(list_attr(
+                Nl80211Attr::ScanFrequencies,
+                self.frequencies.iter().copied(),
+            )?);
+        }
+
+        let mut plans_attr = Nlattr::new(true, false, Nl80211Attr::SchedScanPlans, Buffer::new())?;
+        for (i, plan) in self.plans.iter().enumerate() {
+            let mut plan_attr = Nlattr::new(true, false, i as u16 + 1, Buffer::new())?;
+            plan_attr.add_nested_attribute(&Nlattr::new(
+                false,
+                false,
+                Nl80211SchedScanPlan::Interval,
+                plan.interval.as_secs() as u32,
+            )?)?;
+            if let Some(iterations) = plan.iterations {
+                plan_attr.add_nested_attribute(&Nlattr::new(
+                    false,
+                    false,
+                    Nl80211SchedScanPlan::Iterations,
+                    iterations,
+                )?)?;
+            }
+            plans_attr.add_nested_attribute(&plan_attr)?;
+        }
+        attrs.push(plans_attr);
+
+        if !self.match_sets.is_empty() {
+            let mut matches_attr =
+                Nlattr::new(true, false, Nl80211Attr::SchedScanMatch, Buffer::new())?;
+            for (i, match_set) in self.match_sets.iter().enumerate() {
+                let mut match_attr = Nlattr::new(true, false, i as u16 + 1, Buffer::new())?;
+                if let Some(ssid) = &match_set.ssid {
+                    match_attr.add_nested_attribute(&Nlattr::new(
+                        false,
+                        false,
+                        Nl80211SchedScanMatchAttr::Ssid,
+                        ssid.as_slice(),
+                    )?)?;
+                }
+                if let Some(rssi) = match_set.rssi_threshold_dbm {
+                    match_attr.add_nested_attribute(&Nlattr::new(
+                        false,
+                        false,
+                        Nl80211SchedScanMatchAttr::Rssi,
+                        rssi,
+                    )?)?;
+                }
+                matches_attr.add_nested_attribute(&match_attr)?;
+            }
+            attrs.push(matches_attr);
+        }
+
+        if let Some(relative_rssi) = self.relative_rssi {
+            attrs.push(Nlattr::new(
+                false,
+                false,
+                Nl80211Attr::SchedScanRelativeRssi,
+                relative_rssi,
+            )?);
+        }
+
+        Ok(attrs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wiphy(max_plans: u32, max_iterations: u32) -> Wiphy {
+        Wiphy {
+            name: "phy0".to_owned(),
+            max_sched_scan_plans: max_plans,
+            max_sched_scan_plan_interval: 3600,
+            max_sched_scan_plan_iterations: max_iterations,
+            ..Wiphy::default()
+        }
+    }
+
+    #[test]
+    fn single_plan_driver_accepts_infinite_plan() {
+        let request = SchedScanRequest::new().interval(Duration::from_secs(30));
+
+        assert!(request.validate_plans(&wiphy(1, 0)).is_ok());
+    }
+
+    #[test]
+    fn checks_limited_plan_iterations() {
+        let request = |iterations| {
+            SchedScanRequest::new()
+                .plan(Duration::from_secs(10), iterations)
+                .interval(Duration::from_secs(60))
+        };
+
+        assert!(request(5).validate_plans(&wiphy(2, 5)).is_ok());
+        assert!(request(6).validate_plans(&wiphy(2, 5)).is_err());
+        assert!(request(0).validate_plans(&wiphy(2, 5)).is_err());
+    }
+
+    #[test]
+    fn only_last_plan_runs_forever() {
+        let limited_last = SchedScanRequest::new().plan(Duration::from_secs(10), 3);
+        let infinite_first = SchedScanRequest::new()
+            .interval(Duration::from_secs(10))
+            .interval(Duration::from_secs(60));
+
+        assert!(limited_last.validate_plans(&wiphy(2, 5)).is_err());
+        assert!(infinite_first.validate_plans(&wiphy(2, 5)).is_err());
+        assert!(SchedScanRequest::new()
+            .validate_plans(&wiphy(2, 5))
+            .is_err());
+    }
+}
@@ -0,0 +1,505 @@
+use crate::ie::{self, Element, ParseError, Reader};
+
+const OUI_IEEE80211: [u8; 3] = [0x00, 0x0f, 0xac];
+const OUI_MICROSOFT: [u8; 3] = [0x00, 0x50, 0xf2];
+const WPA_OUI_TYPE: u8 = 1;
+const PMKID_LEN: usize = 16;
+
+/// Cipher suite selector from an RSN or WPA element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cipher {
+    UseGroup,
+    Wep40,
+    Tkip,
+    Ccmp128,
+    Wep104,
+    BipCmac128,
+    GroupNotAllowed,
+    Gcmp128,
+    Gcmp256,
+    Ccmp256,
+    BipGmac128,
+    BipGmac256,
+    BipCmac256,
+    Other { oui: [u8; 3], suite_type: u8 },
+}
+
+impl Cipher {
+    fn from_selector(oui: [u8; 3], suite_type: u8) -> Self {
+        match (oui, suite_type) {
+            (OUI_IEEE80211 | OUI_MICROSOFT, 0) => Cipher::UseGroup,
+            (OUI_IEEE80211 | OUI_MICROSOFT, 1) => Cipher::Wep40,
+            (OUI_IEEE80211 | OUI_MICROSOFT, 2) => Cipher::Tkip,
+            (OUI_IEEE80211 | OUI_MICROSOFT, 4) => Cipher::Ccmp128,
+            (OUI_IEEE80211 | OUI_MICROSOFT, 5) => Cipher::Wep104,
+            (OUI_IEEE80211, 6) => Cipher::BipCmac128,
+            (OUI_IEEE80211, 7) => Cipher::GroupNotAllowed,
+            (OUI_IEEE80211, 8) => Cipher::Gcmp128,
+            (OUI_IEEE80211, 9) => Cipher::Gcmp256,
+            (OUI_IEEE80211, 10) => Cipher::Ccmp256,
+            (OUI_IEEE80211, 11) => Cipher::BipGmac128,
+            (OUI_IEEE80211, 12) => Cipher::BipGmac256,
+            (OUI_IEEE80211, 13) => Cipher::BipCmac256,
+            (oui, suite_type) => Cipher::Other { oui, suite_type },
+        }
+    }
+}
+
+/// Authentication and key management suite selector from an RSN or WPA element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Akm {
+    Ieee8021x,
+    Psk,
+    FtIeee8021x,
+    FtPsk,
+    Ieee8021xSha256,
+    PskSha256,
+    Tdls,
+    Sae,
+    FtSae,
+    ApPeerKey,
+    SuiteB,
+    SuiteB192,
+    FtIeee8021xSha384,
+    FilsSha256,
+    FilsSha384,
+    FtFilsSha256,
+    FtFilsSha384,
+    Owe,
+    FtPskSha384,
+    PskSha384,
+    SaeExtKey,
+    FtSaeExtKey,
+    Other { oui: [u8; 3], suite_type: u8 },
+}
+
+impl Akm {
+    fn from_selector(oui: [u8; 3], suite_type: u8) -> Self {
+        match (oui, suite_type) {
+            (OUI_IEEE80211 | OUI_MICROSOFT, 1) => Akm::Ieee8021x,
+            (OUI_IEEE80211 | OUI_MICROSOFT, 2) => Akm::Psk,
+            (OUI_IEEE80211, 3) => Akm::FtIeee8021x,
+            (OUI_IEEE80211, 4) => Akm::FtPsk,
+            (OUI_IEEE80211, 5) => Akm::Ieee8021xSha256,
+            (OUI_IEEE80211, 6) => Akm::PskSha256,
+            (OUI_IEEE80211, 7) => Akm::Tdls,
+            (OUI_IEEE80211, 8) => Akm::Sae,
+            (OUI_IEEE80211, 9) => Akm::FtSae,
+            (OUI_IEEE80211, 10) => Akm::ApPeerKey,
+            (OUI_IEEE80211, 11) => Akm::SuiteB,
+            (OUI_IEEE80211, 12) => Akm::SuiteB192,
+            (OUI_IEEE80211, 13) => Akm::FtIeee8021xSha384,
+            (OUI_IEEE80211, 14) => Akm::FilsSha256,
+            (OUI_IEEE80211, 15) => Akm::FilsSha384,
+            (OUI_IEEE80211, 16) => Akm::FtFilsSha256,
+            (OUI_IEEE80211, 17) => Akm::FtFilsSha384,
+            (OUI_IEEE80211, 18) => Akm::Owe,
+            (OUI_IEEE80211, 19) => Akm::FtPskSha384,
+            (OUI_IEEE80211, 20) => Akm::PskSha384,
+            (OUI_IEEE80211, 24) => Akm::SaeExtKey,
+            (OUI_IEEE80211, 25) => Akm::FtSaeExtKey,
+            (oui, suite_type) => Akm::Other { oui, suite_type },
+        }
+    }
+
+    pub fn is_psk(&self) -> bool {
+        matches!(
+            self,
+            Akm::Psk | Akm::FtPsk | Akm::PskSha256 | Akm::FtPskSha384 | Akm::PskSha384
+        )
+    }
+
+    pub fn is_sae(&self) -> bool {
+        matches!(
+            self,
+            Akm::Sae | Akm::FtSae | Akm::SaeExtKey | Akm::FtSaeExtKey
+        )
+    }
+
+    pub fn is_enterprise(&self) -> bool {
+        matches!(
+            self,
+            Akm::Ieee8021x
+                | Akm::FtIeee8021x
+                | Akm::Ieee8021xSha256
+                | Akm::SuiteB
+                | Akm::SuiteB192
+                | Akm::FtIeee8021xSha384
+                | Akm::FilsSha256
+                | Akm::FilsSha384
+                | Akm::FtFilsSha256
+                | Akm::FtFilsSha384
+        )
+    }
+
+    fn is_suite_b(&self) -> bool {
+        matches!(self, Akm::SuiteB | Akm::SuiteB192 | Akm::FtIeee8021xSha384)
+    }
+}
+
+/// RSN Capabilities field of the RSN element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RsnCapabilities(pub u16);
+
+impl RsnCapabilities {
+    /// The AP supports pre-authentication, allowing PMKSA caching across BSSes.
+    pub fn preauthentication(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    pub fn no_pairwise(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    pub fn mfp_required(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+
+    pub fn mfp_capable(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+
+    pub fn peerkey(&self) -> bool {
+        self.0 & (1 << 9) != 0
+    }
+
+    pub fn spp_amsdu_capable(&self) -> bool {
+        self.0 & (1 << 10) != 0
+    }
+
+    pub fn spp_amsdu_required(&self) -> bool {
+        self.0 & (1 << 11) != 0
+    }
+
+    pub fn extended_key_id(&self) -> bool {
+        self.0 & (1 << 13) != 0
+    }
+
+    pub fn operating_channel_validation(&self) -> bool {
+        self.0 & (1 << 14) != 0
+    }
+}
+
+/// Contents of an RSN element, or of the Microsoft vendor element used by WPA.
+///
+/// Fields omitted from a truncated element take the defaults from IEEE 802.11 9.4.2.24.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rsn {
+    pub version: u16,
+    pub group_cipher: Cipher,
+    pub pairwise_ciphers: Vec<Cipher>,
+    pub akm_suites: Vec<Akm>,
+    pub capabilities: RsnCapabilities,
+    /// PMKIDs cached by the AP, only present in association frames.
+    pub pmkids: Vec<[u8; PMKID_LEN]>,
+    pub group_management_cipher: Option<Cipher>,
+}
+
+impl Rsn {
+    /// Parses the body of an RSN element (ID 48).
+    pub fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        let invalid = || ParseError::InvalidLength {
+            id: ie::EID_RSN,
+            id_ext: None,
+            len: data.len(),
+        };
+        Self::parse_body(data, Cipher::Ccmp128, true).ok_or_else(invalid)
+    }
+
+    /// Parses the body of a WPA vendor element, starting right after the Microsoft OUI.
+    pub fn parse_wpa(data: &[u8]) -> Result<Self, ParseError> {
+        let invalid = || ParseError::InvalidLength {
+            id: ie::EID_VENDOR_SPECIFIC,
+            id_ext: None,
+            len: data.len() + OUI_MICROSOFT.len(),
+        };
+        match data.split_first() {
+            Some((&WPA_OUI_TYPE, body)) => {
+                Self::parse_body(body, Cipher::Tkip, false).ok_or_else(invalid)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn parse_body(data: &[u8], default_cipher: Cipher, rsn: bool) -> Option<Self> {
+        let mut reader = Reader(data);
+
+        let version = reader.u16()?;
+        let mut parsed = Rsn {
+            version,
+            group_cipher: default_cipher,
+            pairwise_ciphers: vec![default_cipher],
+            akm_suites: vec![Akm::Ieee8021x],
+            capabilities: RsnCapabilities::default(),
+            pmkids: Vec::new(),
+            group_management_cipher: None,
+        };
+
+        if reader.is_empty() {
+            return Some(parsed);
+        }
+        let (oui, suite_type) = reader.selector()?;
+        parsed.group_cipher = Cipher::from_selector(oui, suite_type);
+
+        if reader.is_empty() {
+            return Some(parsed);
+        }
+        parsed.pairwise_ciphers = reader.selectors(Cipher::from_selector)?;
+
+        if reader.is_empty() {
+            return Some(parsed);
+        }
+        parsed.akm_suites = reader.selectors(Akm::from_selector)?;
+
+        if !rsn || reader.is_empty() {
+            return Some(parsed);
+        }
+        parsed.capabilities = RsnCapabilities(reader.u16()?);
+
+        if reader.is_empty() {
+            return Some(parsed);
+        }
+        let pmkid_count = reader.u16()?;
+        for _ in 0..pmkid_count {
+            parsed
+                .pmkids
+                .push(reader.bytes(PMKID_LEN)?.try_into().ok()?);
+        }
+
+        if reader.is_empty() {
+            return Some(parsed);
+        }
+        let (oui, suite_type) = reader.selector()?;
+        parsed.group_management_cipher = Some(Cipher::from_selector(oui, suite_type));
+
+        Some(parsed)
+    }
+}
+
+/// Summarised security of a network, as shown to users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Security {
+    Open,
+    Wep,
+    WpaPersonal,
+    WpaEnterprise,
+    Wpa2Personal,
+    Wpa2Enterprise,
+    Wpa3Personal,
+    /// WPA3 transition mode, accepting both SAE and PSK.
+    Wpa2Wpa3Personal,
+    Wpa3Enterprise,
+    /// Opportunistic Wireless Encryption (Enhanced Open).
+    Owe,
+    /// Protected with AKM suites not covered by the other variants.
+    Unknown,
+}
+
+impl Security {
+    pub fn new(rsn: Option<&Rsn>, wpa: Option<&Rsn>, privacy: bool) -> Self {
+        if let Some(rsn) = rsn {
+            let akms = &rsn.akm_suites;
+            let has_sae = akms.iter().any(Akm::is_sae);
+            let has_psk = akms.iter().any(Akm::is_psk);
+
+            return if has_sae && has_psk {
+                Security::Wpa2Wpa3Personal
+            } else if has_sae {
+                Security::Wpa3Personal
+            } else if has_psk {
+                Security::Wpa2Personal
+            } else if akms.contains(&Akm::Owe) {
+                Security::Owe
+            } else if akms.iter().any(Akm::is_suite_b)
+                || (akms.contains(&Akm::Ieee8021xSha256) && rsn.capabilities.mfp_required())
+            {
+                Security::Wpa3Enterprise
+            } else if akms.iter().any(Akm::is_enterprise) {
+                Security::Wpa2Enterprise
+            } else {
+                Security::Unknown
+            };
+        }
+
+        if let Some(wpa) = wpa {
+            return if wpa.akm_suites.iter().any(Akm::is_psk) {
+                Security::WpaPersonal
+            } else if wpa.akm_suites.iter().any(Akm::is_enterprise) {
+                Security::WpaEnterprise
+            } else {
+                Security::Unknown
+            };
+        }
+
+        if privacy {
+            Security::Wep
+        } else {
+            Security::Open
+        }
+    }
+}
+
+/// Extracts the RSN and WPA elements from a buffer of information elements.
+pub(crate) fn extract_rsn_wpa(ies: &[u8]) -> (Option<Rsn>, Option<Rsn>) {
+    let mut rsn = None;
+    let mut wpa = None;
+
+    for element in ie::parse(ies).filter_map(Result::ok) {
+        match element {
+            Element::Rsn(data) if rsn.is_none() => rsn = Rsn::parse(data).ok(),
+            Element::VendorSpecific {
+                oui: OUI_MICROSOFT,
+                data,
+            } if wpa.is_none() && data.first() == Some(&WPA_OUI_TYPE) => {
+                wpa = Rsn::parse_wpa(data).ok()
+            }
+            _ => {}
+        }
+    }
+
+    (rsn, wpa)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RSN element body of a WPA2-Personal AP: CCMP group and pairwise, PSK, MFP capable.
+    const RSN_WPA2_PSK: [u8; 20] = [
+        0x01, 0x00, // version
+        0x00, 0x0f, 0xac, 0x04, // group cipher
+        0x01, 0x00, 0x00, 0x0f, 0xac, 0x04, // pairwise ciphers
+        0x01, 0x00, 0x00, 0x0f, 0xac, 0x02, // AKM suites
+        0x80, 0x00, // RSN capabilities
+    ];
+
+    fn rsn_with_akms(akms: &[u8], capabilities: u16) -> Vec<u8> {
+        let mut data = vec![0x01, 0x00, 0x00, 0x0f, 0xac, 0x04];
+        data.extend_from_slice(&[0x01, 0x00, 0x00, 0x0f, 0xac, 0x04]);
+        data.extend_from_slice(&(akms.len() as u16).to_le_bytes());
+        for &akm in akms {
+            data.extend_from_slice(&[0x00, 0x0f, 0xac, akm]);
+        }
+        data.extend_from_slice(&capabilities.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn parses_rsn() {
+        let rsn = Rsn::parse(&RSN_WPA2_PSK).unwrap();
+
+        assert_eq!(rsn.version, 1);
+        assert_eq!(rsn.group_cipher, Cipher::Ccmp128);
+        assert_eq!(rsn.pairwise_ciphers, vec![Cipher::Ccmp128]);
+        assert_eq!(rsn.akm_suites, vec![Akm::Psk]);
+        assert!(rsn.capabilities.mfp_capable());
+        assert!(!rsn.capabilities.mfp_required());
+        assert!(rsn.pmkids.is_empty());
+        assert_eq!(rsn.group_management_cipher, None);
+        assert_eq!(
+            Security::new(Some(&rsn), None, true),
+            Security::Wpa2Personal
+        );
+    }
+
+    #[test]
+    fn parses_rsn_pmkids_and_group_management_cipher() {
+        let mut data = RSN_WPA2_PSK.to_vec();
+        data.extend_from_slice(&[0x01, 0x00]);
+        data.extend_from_slice(&[0xab; PMKID_LEN]);
+        data.extend_from_slice(&[0x00, 0x0f, 0xac, 0x06]);
+
+        let rsn = Rsn::parse(&data).unwrap();
+
+        assert_eq!(rsn.pmkids, vec![[0xab; PMKID_LEN]]);
+        assert_eq!(rsn.group_management_cipher, Some(Cipher::BipCmac128));
+    }
+
+    #[test]
+    fn version_only_rsn_uses_defaults() {
+        let rsn = Rsn::parse(&[0x01, 0x00]).unwrap();
+
+        assert_eq!(rsn.group_cipher, Cipher::Ccmp128);
+        assert_eq!(rsn.pairwise_ciphers, vec![Cipher::Ccmp128]);
+        assert_eq!(rsn.akm_suites, vec![Akm::Ieee8021x]);
+    }
+
+    #[test]
+    fn truncated_rsn_is_invalid() {
+        let data = &RSN_WPA2_PSK[..10];
+
+        assert_eq!(
+            Rsn::parse(data),
+            Err(ParseError::InvalidLength {
+                id: ie::EID_RSN,
+                id_ext: None,
+                len: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_wpa() {
+        let data = [
+            0x01, // WPA OUI type
+            0x01, 0x00, // version
+            0x00, 0x50, 0xf2, 0x02, // group cipher
+            0x01, 0x00, 0x00, 0x50, 0xf2, 0x02, // pairwise ciphers
+            0x01, 0x00, 0x00, 0x50, 0xf2, 0x02, // AKM suites
+        ];
+
+        let wpa = Rsn::parse_wpa(&data).unwrap();
+
+        assert_eq!(wpa.group_cipher, Cipher::Tkip);
+        assert_eq!(wpa.pairwise_ciphers, vec![Cipher::Tkip]);
+        assert_eq!(wpa.akm_suites, vec![Akm::Psk]);
+        assert_eq!(Security::new(None, Some(&wpa), true), Security::WpaPersonal);
+    }
+
+    #[test]
+    fn wpa_with_other_oui_type_is_invalid() {
+        assert!(Rsn::parse_wpa(&[0x02, 0x01, 0x00]).is_err());
+    }
+
+    #[test]
+    fn extracts_rsn_and_wpa_elements() {
+        let mut ies = vec![ie::EID_RSN, RSN_WPA2_PSK.len() as u8];
+        ies.extend_from_slice(&RSN_WPA2_PSK);
+        ies.extend_from_slice(&[0xdd, 0x0a, 0x00, 0x50, 0xf2, 0x01, 0x01, 0x00]);
+        ies.extend_from_slice(&[0x00, 0x50, 0xf2, 0x02]);
+
+        let (rsn, wpa) = extract_rsn_wpa(&ies);
+
+        assert_eq!(rsn.unwrap().akm_suites, vec![Akm::Psk]);
+        assert_eq!(wpa.unwrap().group_cipher, Cipher::Tkip);
+    }
+
+    #[test]
+    fn classifies_security() {
+        let security = |akms: &[u8], capabilities: u16| {
+            let rsn = Rsn::parse(&rsn_with_akms(akms, capabilities)).unwrap();
+            Security::new(Some(&rsn), None, true)
+        };
+
+        assert_eq!(security(&[2, 8], 0x0080), Security::Wpa2Wpa3Personal);
+        assert_eq!(security(&[8], 0x00c0), Security::Wpa3Personal);
+        assert_eq!(security(&[18], 0x00c0), Security::Owe);
+        assert_eq!(security(&[1], 0x0000), Security::Wpa2Enterprise);
+        assert_eq!(security(&[12], 0x00c0), Security::Wpa3Enterprise);
+        assert_eq!(security(&[5], 0x00c0), Security::Wpa3Enterprise);
+        assert_eq!(security(&[7], 0x0000), Security::Unknown);
+        assert_eq!(Security::new(None, None, true), Security::Wep);
+        assert_eq!(Security::new(None, None, false), Security::Open);
+    }
+
+    #[test]
+    fn ieee8021x_sha256_without_mfp_required_is_enterprise() {
+        let rsn = Rsn::parse(&rsn_with_akms(&[5], 0x0080)).unwrap();
+
+        assert!(Akm::Ieee8021xSha256.is_enterprise());
+        assert_eq!(
+            Security::new(Some(&rsn), None, true),
+            Security::Wpa2Enterprise
+        );
+    }
+}
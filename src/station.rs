@@ -0,0 +1,498 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::time::Duration;
+
+use macaddr::MacAddr6;
+
+use neli::attr::Attribute;
+use neli::genl::{Genlmsghdr, Nlattr};
+use neli::types::Buffer;
+
+use crate::capabilities::Capabilities;
+use crate::consts;
+use crate::enums::{Nl80211Attr, Nl80211Bss, Nl80211Cmd};
+use crate::error::Error;
+use crate::frequency::{frequency_to_channel, Band};
+use crate::ie::{self, Element};
+use crate::multi_link::extract_multi_link;
+use crate::security::{extract_rsn_wpa, Rsn, Security};
+
+/// Capability Information field of beacons and probe responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BssCapability(pub u16);
+
+impl BssCapability {
+    /// The BSS is an infrastructure network run by an AP.
+    pub fn ess(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    /// The BSS is an ad-hoc network.
+    pub fn ibss(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// Encryption is required. Without an RSN or WPA element this means WEP.
+    pub fn privacy(&self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+
+    pub fn short_preamble(&self) -> bool {
+        self.0 & (1 << 5) != 0
+    }
+
+    pub fn spectrum_management(&self) -> bool {
+        self.0 & (1 << 8) != 0
+    }
+
+    pub fn qos(&self) -> bool {
+        self.0 & (1 << 9) != 0
+    }
+
+    pub fn short_slot_time(&self) -> bool {
+        self.0 & (1 << 10) != 0
+    }
+
+    pub fn radio_measurement(&self) -> bool {
+        self.0 & (1 << 12) != 0
+    }
+}
+
+/// Signal strength of a BSS, in the unit the driver reports it in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Signal in mBm, i.e. 100 * dBm.
+    Mbm(i32),
+    /// Signal in driver-specific units, scaled to 0..=100.
+    Unspec(u8),
+}
+
+impl Signal {
+    pub fn dbm(&self) -> Option<i32> {
+        match self {
+            Signal::Mbm(mbm) => Some(mbm / 100),
+            Signal::Unspec(_) => None,
+        }
+    }
+
+    /// Signal quality in percent.
+    pub fn quality(&self) -> u8 {
+        match self {
+            Signal::Mbm(mbm) => dbm_level_to_quality(*mbm),
+            Signal::Unspec(unspec) => (*unspec).min(100),
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::Mbm(mbm) => write!(f, "{} dBm", mbm / 100),
+            Signal::Unspec(unspec) => write!(f, "{}/100", unspec),
+        }
+    }
+}
+
+/// Signal received by one antenna chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChainSignal {
+    pub chain: u8,
+    pub signal_dbm: i8,
+}
+
+/// Frame the information elements in [`Station::ies`] were taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IeSource {
+    Beacon,
+    ProbeResponse,
+}
+
+/// Relation of the interface to a BSS, only reported for the BSS it is connected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BssStatus {
+    Authenticated,
+    Associated,
+    IbssJoined,
+}
+
+impl BssStatus {
+    pub(crate) fn from_nl80211(status: ::std::os::raw::c_uint) -> Option<Self> {
+        match status {
+            consts::NL80211_BSS_STATUS_AUTHENTICATED => Some(BssStatus::Authenticated),
+            consts::NL80211_BSS_STATUS_ASSOCIATED => Some(BssStatus::Associated),
+            consts::NL80211_BSS_STATUS_IBSS_JOINED => Some(BssStatus::IbssJoined),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Station {
+    /// SSID decoded for display, with invalid UTF-8 sequences replaced.
+    pub ssid: String,
+    /// SSID exactly as advertised by the network.
+    pub ssid_bytes: Vec<u8>,
+    /// Set when the beacon SSID is zero-length or consists only of NUL bytes. The actual SSID
+    /// of a hidden network is still known if it answered a directed probe request.
+    pub hidden: bool,
+    pub bssid: MacAddr6,
+    /// Transmitted BSS advertising this one in its Multiple BSSID element, for BSSes that do
+    /// not beacon themselves.
+    pub parent_bssid: Option<MacAddr6>,
+    /// MAC address of the AP MLD the BSS is affiliated with, for Wi-Fi 7 multi-link APs.
+    pub mld_address: Option<MacAddr6>,
+    /// Link ID of the BSS within its AP MLD.
+    pub mlo_link_id: Option<u8>,
+    /// Centre frequency in MHz.
+    pub frequency: u32,
+    pub channel: Option<u32>,
+    pub band: Option<Band>,
+    /// Signal strength, if the driver reports one.
+    pub signal: Option<Signal>,
+    /// Signal per antenna chain, if the driver reports it.
+    pub chain_signals: Vec<ChainSignal>,
+    pub security: Security,
+    pub rsn: Option<Rsn>,
+    pub wpa: Option<Rsn>,
+    pub capabilities: Capabilities,
+    pub status: Option<BssStatus>,
+    pub bss_capability: BssCapability,
+    /// Beacon interval in time units of 1024 µs.
+    pub beacon_interval: Option<u16>,
+    /// Timing synchronisation function of the AP in µs, from the most recent frame.
+    pub tsf: Option<u64>,
+    /// TSF of the last beacon.
+    pub beacon_tsf: Option<u64>,
+    /// TSF of the parent BSS when the frame was received, for BSSes only reported in the
+    /// frames of another BSS.
+    pub parent_tsf: Option<u64>,
+    /// Most recently received information elements, from a beacon or a probe response.
+    pub ies: Vec<u8>,
+    pub ies_source: IeSource,
+    /// Information elements of the last beacon, if one was received.
+    pub beacon_ies: Option<Vec<u8>>,
+    /// Milliseconds since the BSS was last seen, at the time the results were dumped.
+    pub seen_ms_ago: Option<u32>,
+    /// `CLOCK_BOOTTIME` timestamp in nanoseconds of when the BSS was last seen.
+    pub last_seen_boottime: Option<u64>,
+}
+
+impl TryFrom<&Genlmsghdr<Nl80211Cmd, Nl80211Attr>> for Station {
+    type Error = Error;
+
+    fn try_from(payload: &Genlmsghdr<Nl80211Cmd, Nl80211Attr>) -> Result<Self, Self::Error> {
+        let mut attrs = payload.get_attr_handle();
+        let bss_attrs = attrs.get_nested_attributes::<Nl80211Bss>(Nl80211Attr::Bss)?;
+
+        let bssid_bytes: [u8; 6] = bss_attrs
+            .get_attr_payload_as_with_len::<&[u8]>(Nl80211Bss::Bssid)?
+            .try_into()?;
+        let bssid = bssid_bytes.into();
+
+        let parent_bssid = bss_attrs
+            .get_attr_payload_as_with_len::<&[u8]>(Nl80211Bss::ParentBssid)
+            .ok()
+            .and_then(|bytes| <[u8; 6]>::try_from(bytes).ok())
+            .map(MacAddr6::from);
+
+        let frequency = bss_attrs.get_attr_payload_as::<u32>(Nl80211Bss::Frequency)?;
+        let channel = frequency_to_channel(frequency);
+        let band = Band::from_frequency(frequency);
+
+        let signal = bss_attrs
+            .get_attr_payload_as::<i32>(Nl80211Bss::SignalMbm)
+            .map(Signal::Mbm)
+            .or_else(|_| {
+                bss_attrs
+                    .get_attr_payload_as::<u8>(Nl80211Bss::SignalUnspec)
+                    .map(Signal::Unspec)
+            })
+            .ok();
+        let chain_signals = bss_attrs
+            .get_attribute(Nl80211Bss::ChainSignal)
+            .and_then(|attr| extract_chain_signals(attr).ok())
+            .unwrap_or_default();
+
+        let ies = bss_attrs
+            .get_attribute(Nl80211Bss::InformationElements)
+            .map(|ie_attrs| ie_attrs.payload().as_ref())
+            .unwrap_or_default();
+        let beacon_ies = bss_attrs
+            .get_attribute(Nl80211Bss::BeaconIes)
+            .map(|ie_attrs| ie_attrs.payload().as_ref());
+        let ies_source = if bss_attrs.get_attribute(Nl80211Bss::PrespData).is_some() {
+            IeSource::ProbeResponse
+        } else {
+            IeSource::Beacon
+        };
+
+        let (ssid_bytes, hidden) = select_ssid(ies, beacon_ies, ies_source);
+        let ssid = String::from_utf8_lossy(&ssid_bytes).into_owned();
+
+        let bss_capability = bss_attrs
+            .get_attr_payload_as(Nl80211Bss::Capability)
+            .map(BssCapability)
+            .unwrap_or_default();
+        let (rsn, wpa) = extract_rsn_wpa(ies);
+        let security = Security::new(rsn.as_ref(), wpa.as_ref(), bss_capability.privacy());
+
+        let beacon_interval = bss_attrs
+            .get_attr_payload_as(Nl80211Bss::BeaconInterval)
+            .ok();
+        let tsf = bss_attrs.get_attr_payload_as(Nl80211Bss::Tsf).ok();
+        let beacon_tsf = bss_attrs.get_attr_payload_as(Nl80211Bss::BeaconTsf).ok();
+        let parent_tsf = bss_attrs.get_attr_payload_as(Nl80211Bss::ParentTsf).ok();
+        let capabilities = Capabilities::parse(ies);
+
+        // Kernels without MLO support do not report the MLD, but the beacon still carries it.
+        let multi_link = extract_multi_link(ies);
+        let mld_address = bss_attrs
+            .get_attr_payload_as_with_len::<&[u8]>(Nl80211Bss::MldAddr)
+            .ok()
+            .and_then(|bytes| <[u8; 6]>::try_from(bytes).ok())
+            .map(MacAddr6::from)
+            .or_else(|| multi_link.as_ref().map(|ml| ml.mld_address));
+        let mlo_link_id = bss_attrs
+            .get_attr_payload_as::<u8>(Nl80211Bss::MloLinkId)
+            .ok()
+            .or_else(|| multi_link.as_ref().and_then(|ml| ml.link_id));
+
+        let status = bss_attrs
+            .get_attr_payload_as(Nl80211Bss::Status)
+            .ok()
+            .and_then(BssStatus::from_nl80211);
+
+        let seen_ms_ago = bss_attrs.get_attr_payload_as(Nl80211Bss::SeenMsAgo).ok();
+        let last_seen_boottime = bss_attrs
+            .get_attr_payload_as(Nl80211Bss::LastSeenBoottime)
+            .ok();
+
+        Ok(Station {
+            ssid,
+            ssid_bytes,
+            hidden,
+            bssid,
+            parent_bssid,
+            mld_address,
+            mlo_link_id,
+            frequency,
+            channel,
+            band,
+            signal,
+            chain_signals,
+            security,
+            rsn,
+            wpa,
+            capabilities,
+            status,
+            bss_capability,
+            beacon_interval,
+            tsf,
+            beacon_tsf,
+            parent_tsf,
+            ies: ies.to_vec(),
+            ies_source,
+            beacon_ies: beacon_ies.map(<[u8]>::to_vec),
+            seen_ms_ago,
+            last_seen_boottime,
+        })
+    }
+}
+
+impl Station {
+    /// Signal in dBm, unless the driver reports no signal or only in unspecified units.
+    pub fn signal_dbm(&self) -> Option<i32> {
+        self.signal.as_ref().and_then(Signal::dbm)
+    }
+
+    /// Signal quality in percent.
+    pub fn quality(&self) -> Option<u8> {
+        self.signal.as_ref().map(Signal::quality)
+    }
+
+    /// Time since the AP started, as the TSF counts microseconds from then.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.beacon_tsf.or(self.tsf).map(Duration::from_micros)
+    }
+
+    pub fn elements(&self) -> ie::Elements<'_> {
+        ie::parse(&self.ies)
+    }
+
+    pub fn beacon_elements(&self) -> Option<ie::Elements<'_>> {
+        self.beacon_ies.as_deref().map(ie::parse)
+    }
+}
+
+/// Picks the SSID of a BSS and tells whether the network is hidden. Hidden networks only reveal
+/// their SSID in probe responses, so the beacon decides whether the network is hidden while any
+/// frame revealing the SSID provides it.
+fn select_ssid(ies: &[u8], beacon_ies: Option<&[u8]>, ies_source: IeSource) -> (Vec<u8>, bool) {
+    let ies_ssid = extract_ssid(ies);
+    let beacon_ssid = match (beacon_ies, ies_source) {
+        (Some(beacon_ies), _) => extract_ssid(beacon_ies),
+        (None, IeSource::Beacon) => ies_ssid.clone(),
+        (None, IeSource::ProbeResponse) => None,
+    };
+
+    let hidden = beacon_ssid
+        .as_deref()
+        .or(ies_ssid.as_deref())
+        .map(is_hidden_ssid)
+        .unwrap_or(true);
+    let ssid = [&ies_ssid, &beacon_ssid]
+        .into_iter()
+        .flatten()
+        .find(|ssid| !is_hidden_ssid(ssid))
+        .or(ies_ssid.as_ref())
+        .cloned()
+        .unwrap_or_default();
+
+    (ssid, hidden)
+}
+
+fn is_hidden_ssid(ssid: &[u8]) -> bool {
+    ssid.iter().all(|&b| b == 0)
+}
+
+fn extract_ssid(ies: &[u8]) -> Option<Vec<u8>> {
+    ie::parse(ies)
+        .filter_map(Result::ok)
+        .find_map(|element| match element {
+            Element::Ssid(ssid) => Some(ssid.to_vec()),
+            _ => None,
+        })
+}
+
+fn extract_chain_signals(attr: &Nlattr<Nl80211Bss, Buffer>) -> Result<Vec<ChainSignal>, Error> {
+    attr.get_attr_handle::<u16>()?
+        .iter()
+        .map(|chain| {
+            Ok(ChainSignal {
+                chain: chain.nla_type.nla_type as u8,
+                signal_dbm: chain.get_payload_as::<u8>()? as i8,
+            })
+        })
+        .collect()
+}
+
+fn dbm_level_to_quality(signal: i32) -> u8 {
+    let mut val = f64::from(signal) / 100.;
+    val = val.clamp(-100., -40.);
+    val = (val + 40.).abs();
+    val = (100. - (100. * val) / 60.).round();
+    val = val.clamp(0., 100.);
+    val as u8
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a scan result with the given BSSID and information elements, received in a beacon
+    /// on 2412 MHz.
+    pub(crate) fn station(bssid: MacAddr6, ies: &[u8]) -> Station {
+        Station {
+            ssid: String::new(),
+            ssid_bytes: Vec::new(),
+            hidden: true,
+            bssid,
+            parent_bssid: None,
+            mld_address: None,
+            mlo_link_id: None,
+            frequency: 2412,
+            channel: Some(1),
+            band: Some(Band::Band2GHz),
+            signal: None,
+            chain_signals: Vec::new(),
+            security: Security::Open,
+            rsn: None,
+            wpa: None,
+            capabilities: Capabilities::default(),
+            status: None,
+            bss_capability: BssCapability::default(),
+            beacon_interval: None,
+            tsf: None,
+            beacon_tsf: None,
+            parent_tsf: None,
+            ies: ies.to_vec(),
+            ies_source: IeSource::Beacon,
+            beacon_ies: None,
+            seen_ms_ago: None,
+            last_seen_boottime: None,
+        }
+    }
+
+    #[test]
+    fn visible_ssid() {
+        let ies = [0x00, 0x04, b'h', b'o', b'm', b'e'];
+
+        assert_eq!(
+            select_ssid(&ies, None, IeSource::Beacon),
+            (b"home".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn all_nul_ssid_is_hidden() {
+        let ies = [0x00, 0x04, 0x00, 0x00, 0x00, 0x00];
+
+        assert_eq!(
+            select_ssid(&ies, None, IeSource::Beacon),
+            (vec![0; 4], true)
+        );
+    }
+
+    #[test]
+    fn zero_length_ssid_is_hidden() {
+        assert_eq!(
+            select_ssid(&[0x00, 0x00], None, IeSource::Beacon),
+            (Vec::new(), true)
+        );
+    }
+
+    #[test]
+    fn missing_ssid_is_hidden() {
+        let ies = [ie::EID_DS_PARAMETER_SET, 0x01, 0x06];
+
+        assert_eq!(
+            select_ssid(&ies, None, IeSource::Beacon),
+            (Vec::new(), true)
+        );
+    }
+
+    #[test]
+    fn probe_response_reveals_hidden_ssid() {
+        let probe_response_ies = [0x00, 0x04, b'h', b'o', b'm', b'e'];
+        let beacon_ies = [0x00, 0x00];
+
+        assert_eq!(
+            select_ssid(
+                &probe_response_ies,
+                Some(&beacon_ies),
+                IeSource::ProbeResponse
+            ),
+            (b"home".to_vec(), true)
+        );
+        // Without a beacon, the probe response alone does not make the network hidden.
+        assert_eq!(
+            select_ssid(&probe_response_ies, None, IeSource::ProbeResponse),
+            (b"home".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn derives_signal_dbm_and_quality() {
+        let mut station = station(MacAddr6::nil(), &[]);
+        assert_eq!(station.signal_dbm(), None);
+        assert_eq!(station.quality(), None);
+
+        station.signal = Some(Signal::Mbm(-7000));
+        assert_eq!(station.signal_dbm(), Some(-70));
+        assert_eq!(station.quality(), Some(50));
+
+        station.signal = Some(Signal::Unspec(120));
+        assert_eq!(station.signal_dbm(), None);
+        assert_eq!(station.quality(), Some(100));
+    }
+}
@@ -0,0 +1,184 @@
+use neli::genl::{Genlmsghdr, Nlattr};
+use neli::types::Buffer;
+
+use crate::enums::{Nl80211Attr, Nl80211BandAttr, Nl80211Cmd, Nl80211FrequencyAttr};
+use crate::error::Result;
+use crate::frequency::Band;
+
+/// Capabilities of a physical wireless device, as reported by a split `GetWiphy` dump.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Wiphy {
+    pub index: u32,
+    pub name: String,
+    /// Maximum number of SSIDs that can be probed for in one scan.
+    pub max_scan_ssids: u8,
+    /// Maximum length of extra information elements added to probe requests.
+    pub max_scan_ie_len: u16,
+    /// Maximum number of SSIDs that can be probed for in one scheduled scan.
+    pub max_sched_scan_ssids: u8,
+    /// Maximum number of match sets of a scheduled scan.
+    pub max_match_sets: u8,
+    pub max_sched_scan_plans: u32,
+    /// Maximum interval of a scheduled scan plan, in seconds.
+    pub max_sched_scan_plan_interval: u32,
+    pub max_sched_scan_plan_iterations: u32,
+    pub bands: Vec<WiphyBand>,
+    pub(crate) feature_flags: u32,
+    pub(crate) ext_features: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WiphyBand {
+    pub band: Band,
+    pub frequencies: Vec<WiphyFrequency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WiphyFrequency {
+    /// Centre frequency in MHz.
+    pub frequency: u32,
+    /// Disabled by the regulatory domain, so it cannot be scanned either.
+    pub disabled: bool,
+    /// Initiating radiation is not allowed, so only passive scans are possible.
+    pub no_ir: bool,
+    /// Radar detection is required before transmitting.
+    pub radar: bool,
+}
+
+impl Wiphy {
+    /// Merges one message of a split wiphy dump, which spreads the attributes of a wiphy over
+    /// several messages.
+    pub(crate) fn merge(&mut self, payload: &Genlmsghdr<Nl80211Cmd, Nl80211Attr>) -> Result<()> {
+        let mut attrs = payload.get_attr_handle();
+
+        self.index = attrs.get_attr_payload_as(Nl80211Attr::Wiphy)?;
+
+        if let Ok(name) = attrs.get_attr_payload_as_with_len(Nl80211Attr::WiphyName) {
+            self.name = name;
+        }
+        if let Ok(max_scan_ssids) = attrs.get_attr_payload_as(Nl80211Attr::MaxNumScanSsids) {
+            self.max_scan_ssids = max_scan_ssids;
+        }
+        if let Ok(max_scan_ie_len) = attrs.get_attr_payload_as(Nl80211Attr::MaxScanIeLen) {
+            self.max_scan_ie_len = max_scan_ie_len;
+        }
+
+        if let Ok(max) = attrs.get_attr_payload_as(Nl80211Attr::MaxNumSchedScanSsids) {
+            self.max_sched_scan_ssids = max;
+        }
+        if let Ok(max) = attrs.get_attr_payload_as(Nl80211Attr::MaxMatchSets) {
+            self.max_match_sets = max;
+        }
+        if let Ok(max) = attrs.get_attr_payload_as(Nl80211Attr::MaxNumSchedScanPlans) {
+            self.max_sched_scan_plans = max;
+        }
+        if let Ok(max) = attrs.get_attr_payload_as(Nl80211Attr::MaxScanPlanInterval) {
+            self.max_sched_scan_plan_interval = max;
+        }
+        if let Ok(max) = attrs.get_attr_payload_as(Nl80211Attr::MaxScanPlanIterations) {
+            self.max_sched_scan_plan_iterations = max;
+        }
+        if let Ok(feature_flags) = attrs.get_attr_payload_as(Nl80211Attr::FeatureFlags) {
+            self.feature_flags = feature_flags;
+        }
+        if let Ok(ext_features) =
+            attrs.get_attr_payload_as_with_len::<&[u8]>(Nl80211Attr::ExtFeatures)
+        {
+            self.ext_features = ext_features.to_vec();
+        }
+
+        if let Ok(bands) = attrs.get_nested_attributes::<u16>(Nl80211Attr::WiphyBands) {
+            for band_attr in bands.iter() {
+                self.merge_band(band_attr)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The channels of a band may be split over several messages too. Bands unknown to this
+    /// crate are skipped.
+    fn merge_band(&mut self, band_attr: &Nlattr<u16, Buffer>) -> Result<()> {
+        let band = match Band::from_nl80211(band_attr.nla_type.nla_type as ::std::os::raw::c_uint) {
+            Some(band) => band,
+            None => return Ok(()),
+        };
+        let mut band_attrs = band_attr.get_attr_handle::<Nl80211BandAttr>()?;
+
+        let index = match self.bands.iter().position(|b| b.band == band) {
+            Some(index) => index,
+            None => {
+                self.bands.push(WiphyBand {
+                    band,
+                    frequencies: Vec::new(),
+                });
+                self.bands.len() - 1
+            }
+        };
+
+        if let Ok(freqs) = band_attrs.get_nested_attributes::<u16>(Nl80211BandAttr::Freqs) {
+            for freq_attr in freqs.iter() {
+                let freq_attrs = freq_attr.get_attr_handle::<Nl80211FrequencyAttr>()?;
+                self.bands[index].frequencies.push(WiphyFrequency {
+                    frequency: freq_attrs.get_attr_payload_as(Nl80211FrequencyAttr::Freq)?,
+                    disabled: freq_attrs
+                        .get_attribute(Nl80211FrequencyAttr::Disabled)
+                        .is_some(),
+                    no_ir: freq_attrs
+                        .get_attribute(Nl80211FrequencyAttr::NoIr)
+                        .is_some(),
+                    radar: freq_attrs
+                        .get_attribute(Nl80211FrequencyAttr::Radar)
+                        .is_some(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks up a channel of the wiphy by its centre frequency in MHz.
+    pub fn frequency(&self, frequency: u32) -> Option<&WiphyFrequency> {
+        self.bands
+            .iter()
+            .flat_map(|band| &band.frequencies)
+            .find(|freq| freq.frequency == frequency)
+    }
+
+    /// Checks an `NL80211_FEATURE_*` flag.
+    pub(crate) fn has_feature(&self, feature: u32) -> bool {
+        self.feature_flags & feature != 0
+    }
+
+    /// Checks an `NL80211_EXT_FEATURE_*` index in the extended features bitmap.
+    pub(crate) fn has_ext_feature(&self, index: u32) -> bool {
+        self.ext_features
+            .get(index as usize / 8)
+            .map(|byte| byte & (1 << (index % 8)) != 0)
+            .unwrap_or_default()
+    }
+
+    pub fn band(&self, band: Band) -> Option<&WiphyBand> {
+        self.bands.iter().find(|b| b.band == band)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ext_feature_index_selects_byte_and_bit() {
+        let wiphy = Wiphy {
+            ext_features: vec![0x00, 0x01, 0x80],
+            ..Wiphy::default()
+        };
+
+        assert!(wiphy.has_ext_feature(8));
+        assert!(wiphy.has_ext_feature(23));
+        assert!(!wiphy.has_ext_feature(0));
+        assert!(!wiphy.has_ext_feature(7));
+        assert!(!wiphy.has_ext_feature(9));
+        assert!(!wiphy.has_ext_feature(24));
+    }
+}